@@ -0,0 +1,512 @@
+use crate::{DetectionResult, Detector, FileFinch, FileType};
+use flate2::bufread::DeflateDecoder;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read};
+use std::ops::ControlFlow;
+
+/// Enough of each entry to tell every supported format apart.
+pub const DEFAULT_PREFIX_LEN: usize = 8192;
+
+pub(crate) const LOCAL_FILE_HEADER: u32 = 0x0403_4b50;
+pub(crate) const CENTRAL_DIRECTORY_HEADER: u32 = 0x0201_4b50;
+pub(crate) const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+pub(crate) const DATA_DESCRIPTOR: u32 = 0x0807_4b50;
+
+pub(crate) const FLAG_DATA_DESCRIPTOR: u16 = 0x0008;
+pub(crate) const METHOD_STORED: u16 = 0;
+pub(crate) const METHOD_DEFLATED: u16 = 8;
+
+#[derive(Debug)]
+pub enum ArchiveError {
+    Io(io::Error),
+    InvalidSignature(u32),
+    UnsupportedCompression { entry: String, method: u16 },
+    UnknownEntrySize(String),
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveError::Io(e) => write!(f, "IO error: {}", e),
+            ArchiveError::InvalidSignature(sig) => write!(f, "Invalid signature: {:#010x}", sig),
+            ArchiveError::UnsupportedCompression { entry, method } => {
+                write!(f, "Unsupported compression method {} for {}", method, entry)
+            }
+            ArchiveError::UnknownEntrySize(entry) => {
+                write!(f, "Cannot find the end of stored entry {}", entry)
+            }
+        }
+    }
+}
+
+impl Error for ArchiveError {}
+
+impl From<io::Error> for ArchiveError {
+    fn from(e: io::Error) -> Self {
+        ArchiveError::Io(e)
+    }
+}
+
+/// Streams through a ZIP archive and calls `on_entry` with each file's name, the first
+/// `prefix_len` decompressed bytes and the detected type.
+///
+/// Only the prefix of each entry is decompressed, the rest is skipped. Entries compressed with a
+/// method other than stored or deflate get an empty prefix (and so `Unknown`), unless their size
+/// is only given after the data, in which case the walk cannot go on past them. Returning
+/// `ControlFlow::Break` stops the walk straight away and its value is handed back, so a caller can
+/// stop at the first entry it cares about without reading the remainder of the archive.
+///
+/// Types come from the shared default [`Detector`], use [`walk_with`] for another one.
+pub fn walk<R, B, F>(
+    reader: R,
+    prefix_len: usize,
+    mut on_entry: F,
+) -> Result<Option<B>, ArchiveError>
+where
+    R: Read,
+    F: FnMut(&str, &[u8], FileType) -> ControlFlow<B>,
+{
+    walk_with(
+        FileFinch::detector(),
+        reader,
+        prefix_len,
+        |name, prefix, result| on_entry(name, prefix, result.file_type),
+    )
+}
+
+/// [`walk`] with entries analyzed by `detector`, so `on_entry` also learns why an entry is
+/// `Unknown`, e.g. that it is empty.
+pub fn walk_with<R, B, F>(
+    detector: &Detector,
+    reader: R,
+    prefix_len: usize,
+    mut on_entry: F,
+) -> Result<Option<B>, ArchiveError>
+where
+    R: Read,
+    F: FnMut(&str, &[u8], DetectionResult) -> ControlFlow<B>,
+{
+    let mut reader = BufReader::new(reader);
+
+    loop {
+        let signature = match read_u32(&mut reader) {
+            Ok(signature) => signature,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        match signature {
+            LOCAL_FILE_HEADER => {}
+            CENTRAL_DIRECTORY_HEADER | END_OF_CENTRAL_DIRECTORY => return Ok(None),
+            other => return Err(ArchiveError::InvalidSignature(other)),
+        }
+
+        let mut header = LocalHeader::read(&mut reader)?;
+
+        if header.name.ends_with('/') {
+            // Nothing to detect, just step over the (normally empty) data and any descriptor.
+            header.read_prefix(&mut reader, 0)?;
+            header.skip_rest(&mut reader)?;
+            continue;
+        }
+
+        let prefix = header.read_prefix(&mut reader, prefix_len)?;
+        let result = detector.analyze_from_path(&header.name, &prefix);
+        if let ControlFlow::Break(value) = on_entry(&header.name, &prefix, result) {
+            return Ok(Some(value));
+        }
+
+        header.skip_rest(&mut reader)?;
+    }
+}
+
+/// Names of the entries whose local headers fall inside `prefix`, the start of a ZIP archive.
+///
+/// Entries with known sizes are stepped over. When the sizes are only in a data descriptor the
+/// next local header signature is searched for instead.
+pub(crate) fn entry_names(prefix: &[u8]) -> Vec<&[u8]> {
+    let signature = LOCAL_FILE_HEADER.to_le_bytes();
+    let mut names = Vec::new();
+    let mut pos = 0;
+
+    while prefix[pos..].starts_with(&signature) {
+        let Some(fixed) = prefix.get(pos + 4..pos + 30) else {
+            break;
+        };
+        let u16_at = |i: usize| u16::from_le_bytes([fixed[i], fixed[i + 1]]) as usize;
+        let compressed_size = u32::from_le_bytes([fixed[14], fixed[15], fixed[16], fixed[17]]);
+
+        let name_start = pos + 30;
+        let Some(name) = prefix.get(name_start..name_start + u16_at(22)) else {
+            break;
+        };
+        names.push(name);
+
+        let data_start = name_start + name.len() + u16_at(24);
+        let flags = u16::from_le_bytes([fixed[2], fixed[3]]);
+        let sized = flags & FLAG_DATA_DESCRIPTOR == 0 && compressed_size != u32::MAX;
+        let next = if sized {
+            data_start + compressed_size as usize
+        } else {
+            data_start
+        };
+
+        pos = match prefix.get(next..) {
+            Some(rest) if rest.starts_with(&signature) => next,
+            Some(rest) => match rest.windows(4).position(|window| window == signature) {
+                Some(offset) => next + offset,
+                None => break,
+            },
+            None => break,
+        };
+    }
+
+    names
+}
+
+struct LocalHeader {
+    name: String,
+    flags: u16,
+    method: u16,
+    compressed_size: u64,
+    zip64: bool,
+    // Whatever of the compressed data is still unread once the prefix has been taken.
+    remaining: u64,
+    // Set when finding the end of the data meant reading the data descriptor too.
+    descriptor_read: bool,
+}
+
+impl LocalHeader {
+    fn read<R: BufRead>(reader: &mut R) -> Result<Self, ArchiveError> {
+        let mut fixed = [0u8; 26];
+        reader.read_exact(&mut fixed)?;
+
+        let u16_at = |i: usize| u16::from_le_bytes([fixed[i], fixed[i + 1]]);
+        let u32_at =
+            |i: usize| u32::from_le_bytes([fixed[i], fixed[i + 1], fixed[i + 2], fixed[i + 3]]);
+
+        let mut name = vec![0u8; u16_at(22) as usize];
+        reader.read_exact(&mut name)?;
+        let mut extra = vec![0u8; u16_at(24) as usize];
+        reader.read_exact(&mut extra)?;
+
+        let zip64 = zip64_field(&extra);
+        let mut compressed_size = u32_at(14) as u64;
+        if compressed_size == u32::MAX as u64 {
+            // The Zip64 extra field holds the uncompressed then the compressed size.
+            compressed_size = zip64
+                .and_then(|field| field.get(8..16))
+                .map_or(0, |size| u64::from_le_bytes(size.try_into().unwrap()));
+        }
+
+        Ok(LocalHeader {
+            name: String::from_utf8_lossy(&name).into_owned(),
+            flags: u16_at(2),
+            method: u16_at(4),
+            compressed_size,
+            zip64: zip64.is_some(),
+            remaining: 0,
+            descriptor_read: false,
+        })
+    }
+
+    fn has_descriptor(&self) -> bool {
+        self.flags & FLAG_DATA_DESCRIPTOR != 0
+    }
+
+    fn read_prefix<R: BufRead>(
+        &mut self,
+        reader: &mut R,
+        prefix_len: usize,
+    ) -> Result<Vec<u8>, ArchiveError> {
+        let mut prefix = Vec::new();
+
+        match self.method {
+            METHOD_STORED if self.has_descriptor() && self.compressed_size == 0 => {
+                self.read_until_descriptor(reader, prefix_len, &mut prefix)?;
+            }
+            METHOD_STORED => {
+                let wanted = self.compressed_size.min(prefix_len as u64);
+                reader.by_ref().take(wanted).read_to_end(&mut prefix)?;
+                self.remaining = self.compressed_size - prefix.len() as u64;
+            }
+            METHOD_DEFLATED if self.has_descriptor() => {
+                // Sizes come after the data, so let the deflate stream tell us where it ends.
+                let mut decoder = DeflateDecoder::new(reader);
+                decoder
+                    .by_ref()
+                    .take(prefix_len as u64)
+                    .read_to_end(&mut prefix)?;
+                io::copy(&mut decoder, &mut io::sink())?;
+            }
+            METHOD_DEFLATED => {
+                let mut decoder = DeflateDecoder::new(reader.by_ref().take(self.compressed_size));
+                decoder
+                    .by_ref()
+                    .take(prefix_len as u64)
+                    .read_to_end(&mut prefix)?;
+                self.remaining = decoder.into_inner().limit();
+            }
+            // Nothing can be read, but with the size known the entry can still be stepped over.
+            _ if !self.has_descriptor() => self.remaining = self.compressed_size,
+            method => {
+                return Err(ArchiveError::UnsupportedCompression {
+                    entry: self.name.clone(),
+                    method,
+                });
+            }
+        }
+
+        Ok(prefix)
+    }
+
+    /// Stored entries written to a stream have no sizes up front, so their data runs until a data
+    /// descriptor whose sizes match the number of bytes before it. Only descriptors with their
+    /// optional signature can be found this way.
+    fn read_until_descriptor<R: BufRead>(
+        &mut self,
+        reader: &mut R,
+        prefix_len: usize,
+        prefix: &mut Vec<u8>,
+    ) -> Result<(), ArchiveError> {
+        let size_len = if self.zip64 { 8 } else { 4 };
+        let descriptor_len = 8 + 2 * size_len;
+        let mut window = VecDeque::with_capacity(descriptor_len + 1);
+        let mut data_len = 0u64;
+
+        for byte in reader.by_ref().bytes() {
+            window.push_back(byte?);
+            if window.len() > descriptor_len {
+                let byte = window.pop_front().expect("window is not empty");
+                if prefix.len() < prefix_len {
+                    prefix.push(byte);
+                }
+                data_len += 1;
+            }
+
+            if window.len() == descriptor_len
+                && ends_stored_data(window.make_contiguous(), size_len, data_len)
+            {
+                self.descriptor_read = true;
+                return Ok(());
+            }
+        }
+
+        Err(ArchiveError::UnknownEntrySize(self.name.clone()))
+    }
+
+    fn skip_rest<R: BufRead>(&self, reader: &mut R) -> Result<(), ArchiveError> {
+        io::copy(&mut reader.by_ref().take(self.remaining), &mut io::sink())?;
+
+        if self.has_descriptor() && !self.descriptor_read {
+            // CRC and sizes, optionally preceded by their own signature.
+            let sizes = if self.zip64 { 16 } else { 8 };
+            let first = read_u32(reader)?;
+            let rest = if first == DATA_DESCRIPTOR {
+                4 + sizes
+            } else {
+                sizes
+            };
+            io::copy(&mut reader.by_ref().take(rest), &mut io::sink())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether `descriptor` is a signed data descriptor for `data_len` bytes of stored data.
+fn ends_stored_data(descriptor: &[u8], size_len: usize, data_len: u64) -> bool {
+    let size_at = |at: usize| {
+        descriptor[at..at + size_len]
+            .iter()
+            .rev()
+            .fold(0u64, |size, &byte| size << 8 | byte as u64)
+    };
+
+    descriptor[..4] == DATA_DESCRIPTOR.to_le_bytes()
+        && size_at(8) == data_len
+        && size_at(8 + size_len) == data_len
+}
+
+fn zip64_field(extra: &[u8]) -> Option<&[u8]> {
+    let mut rest = extra;
+    while rest.len() >= 4 {
+        let id = u16::from_le_bytes([rest[0], rest[1]]);
+        let len = u16::from_le_bytes([rest[2], rest[3]]) as usize;
+        let data = rest.get(4..4 + len)?;
+        if id == 0x0001 {
+            return Some(data);
+        }
+        rest = &rest[4 + len..];
+    }
+    None
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples::{self, ZipEntry};
+    use crate::{DetectionStatus, Profile};
+
+    fn sample_zip() -> Vec<u8> {
+        examples::zip_entries(&[
+            ZipEntry::new("data/", b""),
+            ZipEntry::new("data/sites.csv", b"id,name\n1,a\n2,b\n"),
+            ZipEntry::new("data/areas.gpkg", b"SQLite format 3\x00....").with_deflate(),
+            ZipEntry::new(
+                "data/notes.geojson",
+                br#"{"type":"Feature","geometry":null}"#,
+            )
+            .with_deflate()
+            .with_descriptor(),
+        ])
+    }
+
+    #[test]
+    fn test_walk_all_entries() {
+        let mut seen = Vec::new();
+        let result = walk(
+            &sample_zip()[..],
+            DEFAULT_PREFIX_LEN,
+            |name, _, file_type| {
+                seen.push((name.to_string(), file_type));
+                ControlFlow::<()>::Continue(())
+            },
+        );
+
+        assert!(result.unwrap().is_none());
+        assert_eq!(
+            seen,
+            vec![
+                ("data/sites.csv".to_string(), FileType::Csv),
+                ("data/areas.gpkg".to_string(), FileType::Geopackage),
+                ("data/notes.geojson".to_string(), FileType::Geojson),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_walk_stops_on_break() {
+        let mut visited = 0;
+        let found = walk(&sample_zip()[..], 16, |name, prefix, file_type| {
+            visited += 1;
+            assert!(prefix.len() <= 16);
+            match file_type {
+                FileType::Geopackage => ControlFlow::Break(name.to_string()),
+                _ => ControlFlow::Continue(()),
+            }
+        });
+
+        assert_eq!(found.unwrap(), Some("data/areas.gpkg".to_string()));
+        assert_eq!(visited, 2);
+    }
+
+    #[test]
+    fn test_walk_stored_entries_with_descriptors() {
+        // As written by zipfile.ZIP_STORED to a non-seekable stream: sizes only in descriptors.
+        let png = &FileType::Png.examples()[0];
+        let zip = examples::zip_entries(&[
+            ZipEntry::new("out/", b"").with_descriptor(),
+            ZipEntry::new("out/sites.csv", b"id,name\n1,a\n2,b\n").with_descriptor(),
+            ZipEntry::new("out/empty.txt", b"").with_descriptor(),
+            ZipEntry::new("out/png.png", png).with_descriptor(),
+        ]);
+
+        let mut seen = Vec::new();
+        let result = walk(&zip[..], DEFAULT_PREFIX_LEN, |name, prefix, file_type| {
+            seen.push((name.to_string(), prefix.len(), file_type));
+            ControlFlow::<()>::Continue(())
+        });
+
+        assert!(result.unwrap().is_none());
+        assert_eq!(
+            seen,
+            vec![
+                ("out/sites.csv".to_string(), 16, FileType::Csv),
+                ("out/empty.txt".to_string(), 0, FileType::Unknown),
+                ("out/png.png".to_string(), png.len(), FileType::Png),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_walk_skips_unsupported_compression() {
+        const METHOD_BZIP2: u16 = 12;
+        let zip = examples::zip_entries(&[
+            ZipEntry::new("data/sites.csv.bz2", b"BZh91AY&SY....").with_method(METHOD_BZIP2),
+            ZipEntry::new("data/sites.csv", b"id,name\n1,a\n2,b\n"),
+        ]);
+
+        let mut seen = Vec::new();
+        let result = walk(&zip[..], DEFAULT_PREFIX_LEN, |name, prefix, file_type| {
+            seen.push((name.to_string(), prefix.len(), file_type));
+            ControlFlow::<()>::Continue(())
+        });
+
+        assert!(result.unwrap().is_none());
+        assert_eq!(
+            seen,
+            vec![
+                ("data/sites.csv.bz2".to_string(), 0, FileType::Unknown),
+                ("data/sites.csv".to_string(), 16, FileType::Csv),
+            ]
+        );
+
+        let zip = examples::zip_entries(&[ZipEntry::new("streamed.bz2", b"BZh91AY&SY....")
+            .with_method(METHOD_BZIP2)
+            .with_descriptor()]);
+        let result = walk(&zip[..], DEFAULT_PREFIX_LEN, |_, _, _| {
+            ControlFlow::<()>::Continue(())
+        });
+        assert!(matches!(
+            result,
+            Err(ArchiveError::UnsupportedCompression { method: 12, .. })
+        ));
+    }
+
+    #[test]
+    fn test_walk_with_detector_reports_status() {
+        let zip = examples::zip(&[
+            ("export/sites.tsv", b"id\tname\n1\talpha\n2\tbeta\n"),
+            ("export/empty.csv", b""),
+        ]);
+        let analyze = |detector: &Detector| {
+            let mut seen = Vec::new();
+            walk_with(detector, &zip[..], DEFAULT_PREFIX_LEN, |_, _, result| {
+                seen.push((result.file_type, result.status));
+                ControlFlow::<()>::Continue(())
+            })
+            .unwrap();
+            seen
+        };
+
+        assert_eq!(
+            analyze(FileFinch::detector()),
+            vec![
+                (FileType::Unknown, DetectionStatus::Unrecognised),
+                (FileType::Unknown, DetectionStatus::Empty),
+            ]
+        );
+        assert_eq!(
+            analyze(&Detector::new().with_profile(Profile::DataLake))[0],
+            (FileType::Csv, DetectionStatus::Detected)
+        );
+    }
+
+    #[test]
+    fn test_walk_rejects_non_zip() {
+        let result = walk(&b"not a zip file"[..], DEFAULT_PREFIX_LEN, |_, _, _| {
+            ControlFlow::<()>::Continue(())
+        });
+
+        assert!(matches!(result, Err(ArchiveError::InvalidSignature(_))));
+    }
+}
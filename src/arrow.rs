@@ -0,0 +1,223 @@
+// Just enough of the Arrow IPC format (flatbuffer message headers) to recognise streams and spot
+// compressed record batch bodies in a prefix of the data.
+
+use std::fmt;
+
+const CONTINUATION: [u8; 4] = [0xFF; 4];
+const MAX_METADATA_LEN: usize = 0x100000;
+
+// Message.header union tags.
+const HEADER_SCHEMA: u8 = 1;
+const HEADER_DICTIONARY_BATCH: u8 = 2;
+const HEADER_RECORD_BATCH: u8 = 3;
+const HEADER_SPARSE_TENSOR: u8 = 5;
+
+// Newest MetadataVersion (V5) is 4.
+const MAX_METADATA_VERSION: i16 = 4;
+
+/// How record batch bodies are compressed. Reading them needs an Arrow build with the matching
+/// codec enabled.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum ArrowCompression {
+    Lz4Frame,
+    Zstd,
+}
+
+impl fmt::Display for ArrowCompression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ArrowCompression::Lz4Frame => "LZ4 frame",
+            ArrowCompression::Zstd => "ZSTD",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Whether `bytes` starts with a continuation-prefixed IPC stream message. When the whole
+/// message header is present it has to parse as one, otherwise a plausible length is enough.
+pub(crate) fn is_stream(bytes: &[u8]) -> bool {
+    if !bytes.starts_with(&CONTINUATION) {
+        return false;
+    }
+
+    let Some(len) = u32_at(bytes, 4).map(|len| len as usize) else {
+        return false;
+    };
+    if !(8..MAX_METADATA_LEN).contains(&len) {
+        return false;
+    }
+
+    match bytes.get(8..8 + len) {
+        Some(metadata) => Message::parse(metadata).is_some(),
+        None => u32_at(bytes, 8).is_some_and(|root| (root as usize) < len),
+    }
+}
+
+/// The body compression declared by the first record or dictionary batch found in `bytes`.
+///
+/// Compression is declared per batch rather than in the schema, so this walks the messages that
+/// fit in the prefix (stepping over bodies) until it reaches a batch header. Works for both the
+/// stream format and the file format (Feather V2).
+pub(crate) fn body_compression(bytes: &[u8]) -> Option<ArrowCompression> {
+    let mut pos = if bytes.starts_with(b"ARROW1") { 8 } else { 0 };
+
+    loop {
+        let (message, next) = read_message(bytes, pos)?;
+        let batch = match message.header_type {
+            HEADER_RECORD_BATCH => message.header,
+            HEADER_DICTIONARY_BATCH => message.header.table(1)?,
+            _ => {
+                pos = next;
+                continue;
+            }
+        };
+
+        return batch.table(3).map(|compression| match compression.u8(0) {
+            Some(1) => ArrowCompression::Zstd,
+            _ => ArrowCompression::Lz4Frame,
+        });
+    }
+}
+
+fn read_message(bytes: &[u8], mut pos: usize) -> Option<(Message<'_>, usize)> {
+    if bytes.get(pos..pos + 4)? == CONTINUATION {
+        pos += 4;
+    }
+
+    let len = u32_at(bytes, pos)? as usize;
+    pos += 4;
+    if len == 0 || len >= MAX_METADATA_LEN {
+        return None;
+    }
+
+    let message = Message::parse(bytes.get(pos..pos + len)?)?;
+    let next = (pos + len).checked_add(usize::try_from(message.body_len).ok()?)?;
+    Some((message, next))
+}
+
+struct Message<'a> {
+    header_type: u8,
+    header: Table<'a>,
+    body_len: i64,
+}
+
+impl<'a> Message<'a> {
+    fn parse(metadata: &'a [u8]) -> Option<Self> {
+        let table = Table::root(metadata)?;
+        let version = table.i16(0).unwrap_or(0);
+        let header_type = table.u8(1)?;
+
+        if !(0..=MAX_METADATA_VERSION).contains(&version)
+            || !(HEADER_SCHEMA..=HEADER_SPARSE_TENSOR).contains(&header_type)
+        {
+            return None;
+        }
+
+        Some(Message {
+            header_type,
+            header: table.table(2)?,
+            body_len: table.i64(3).unwrap_or(0),
+        })
+    }
+}
+
+/// A flatbuffer table, read without any schema code generation.
+#[derive(Clone, Copy)]
+struct Table<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Table<'a> {
+    fn root(buf: &'a [u8]) -> Option<Self> {
+        let table = Table {
+            buf,
+            pos: u32_at(buf, 0)? as usize,
+        };
+        table.vtable().map(|_| table)
+    }
+
+    fn vtable(&self) -> Option<usize> {
+        let offset = i32::from_le_bytes(self.buf.get(self.pos..self.pos + 4)?.try_into().ok()?);
+        let vtable = usize::try_from(self.pos as i64 - offset as i64).ok()?;
+        u16_at(self.buf, vtable)?;
+        Some(vtable)
+    }
+
+    fn field(&self, index: usize) -> Option<usize> {
+        let vtable = self.vtable()?;
+        let entry = 4 + 2 * index;
+        if entry + 2 > u16_at(self.buf, vtable)? as usize {
+            return None;
+        }
+        match u16_at(self.buf, vtable + entry)? {
+            0 => None,
+            offset => Some(self.pos + offset as usize),
+        }
+    }
+
+    fn u8(&self, index: usize) -> Option<u8> {
+        self.buf.get(self.field(index)?).copied()
+    }
+
+    fn i16(&self, index: usize) -> Option<i16> {
+        u16_at(self.buf, self.field(index)?).map(|value| value as i16)
+    }
+
+    fn i64(&self, index: usize) -> Option<i64> {
+        let pos = self.field(index)?;
+        Some(i64::from_le_bytes(
+            self.buf.get(pos..pos + 8)?.try_into().ok()?,
+        ))
+    }
+
+    fn table(&self, index: usize) -> Option<Table<'a>> {
+        let pos = self.field(index)?;
+        let pos = pos.checked_add(u32_at(self.buf, pos)? as usize)?;
+        let table = Table { buf: self.buf, pos };
+        table.vtable().map(|_| table)
+    }
+}
+
+fn u16_at(buf: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(buf.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn u32_at(buf: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(buf.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples::{self, IpcHeader};
+
+    #[test]
+    fn test_is_stream() {
+        let stream = examples::truncated_arrow_stream(None);
+        assert!(is_stream(&stream));
+        assert!(is_stream(&stream[..12]));
+        assert!(!is_stream(b"\xFF\xFF\xFF\xFF\x00\x00\x00\x00"));
+        assert!(!is_stream(b"\xFF\xFF\xFF\xFFnot a message at all"));
+    }
+
+    #[test]
+    fn test_body_compression() {
+        for compression in [ArrowCompression::Zstd, ArrowCompression::Lz4Frame] {
+            let stream = examples::truncated_arrow_stream(Some(compression));
+            assert_eq!(body_compression(&stream), Some(compression));
+        }
+        assert_eq!(
+            body_compression(&examples::truncated_arrow_stream(None)),
+            None
+        );
+
+        let mut file = b"ARROW1\x00\x00".to_vec();
+        file.extend_from_slice(&examples::ipc_message(IpcHeader::Schema, 0));
+        file.extend_from_slice(&examples::ipc_message(
+            IpcHeader::RecordBatch(Some(ArrowCompression::Zstd)),
+            4096,
+        ));
+        assert_eq!(body_compression(&file), Some(ArrowCompression::Zstd));
+    }
+}
@@ -0,0 +1,334 @@
+use crate::detector::DETECTION_REVISION;
+use crate::{DetectionResult, Detector, FileFinch, FileType, Profile};
+use std::collections::HashMap;
+use std::fs::{self, Metadata};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+fn header(profile: Profile) -> String {
+    format!(
+        "# file_finch {} detection {} profile {:?}",
+        env!("CARGO_PKG_VERSION"),
+        DETECTION_REVISION,
+        profile
+    )
+}
+
+/// What a file looked like when it was last detected. Any change in size or modification time
+/// means it has to be detected again.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Fingerprint {
+    size: u64,
+    modified_nanos: u128,
+}
+
+impl Fingerprint {
+    fn of(metadata: &Metadata) -> Option<Self> {
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Fingerprint {
+            size: metadata.len(),
+            modified_nanos: modified.as_nanos(),
+        })
+    }
+}
+
+/// Detection results keyed by path, size and modification time, so re-scans only detect files
+/// that changed.
+///
+/// Stored on disk as a version header followed by one tab separated `size`, `mtime`, `type`,
+/// `status`, `detail` (empty when there is none), `path` line per file. A cache written by another
+/// version of the crate or another detection revision is ignored, since its results may be out of
+/// date. The header also names the [`Profile`] the results were detected under, and they are
+/// dropped on the next [`DetectionCache::detect`] if the cache's detector has a different one.
+/// Paths that are not UTF-8 or that contain tabs or line breaks cannot be written on one line and
+/// are never saved.
+#[derive(Debug, Default)]
+pub struct DetectionCache {
+    entries: HashMap<PathBuf, (Fingerprint, DetectionResult)>,
+    profile: Profile,
+    detector: Option<Detector>,
+    hits: usize,
+    misses: usize,
+}
+
+impl DetectionCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a cache file. A missing file gives an empty cache and malformed lines are dropped.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut cache = Self::new();
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(cache),
+            Err(e) => return Err(e),
+        };
+
+        let mut lines = BufReader::new(file).lines();
+        let first = lines.next().transpose()?;
+        let Some(profile) = Profile::ALL
+            .into_iter()
+            .find(|&profile| first.as_ref() == Some(&header(profile)))
+        else {
+            return Ok(cache);
+        };
+        cache.profile = profile;
+
+        for line in lines {
+            let line = line?;
+            let fields: Vec<&str> = line.splitn(6, '\t').collect();
+            let &[size, modified, file_type, status, detail, file_path] = &fields[..] else {
+                continue;
+            };
+            let detail = match detail {
+                "" => Ok(None),
+                detail => detail.parse().map(Some),
+            };
+
+            if let (Ok(size), Ok(modified_nanos), Ok(file_type), Ok(status), Ok(detail)) = (
+                size.parse(),
+                modified.parse(),
+                file_type.parse(),
+                status.parse(),
+                detail,
+            ) {
+                let fingerprint = Fingerprint {
+                    size,
+                    modified_nanos,
+                };
+                let result = DetectionResult {
+                    file_type,
+                    status,
+                    detail,
+                };
+                cache
+                    .entries
+                    .insert(PathBuf::from(file_path), (fingerprint, result));
+            }
+        }
+
+        Ok(cache)
+    }
+
+    /// Detects files with `detector` rather than the shared default one.
+    pub fn with_detector(mut self, detector: Detector) -> Self {
+        self.detector = Some(detector);
+        self
+    }
+
+    /// Writes the cache to `path`, creating parent directories. The file is replaced in one
+    /// rename so an interrupted save never leaves a half written cache.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let tmp = path.with_extension("tmp");
+        let mut out = io::BufWriter::new(fs::File::create(&tmp)?);
+        writeln!(out, "{}", header(self.profile))?;
+        for (file_path, (fingerprint, result)) in &self.entries {
+            let Some(file_path) = file_path
+                .to_str()
+                .filter(|p| !p.contains(['\t', '\n', '\r']))
+            else {
+                continue;
+            };
+            let detail = result.detail.map(|d| d.to_string()).unwrap_or_default();
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                fingerprint.size,
+                fingerprint.modified_nanos,
+                result.file_type,
+                result.status,
+                detail,
+                file_path
+            )?;
+        }
+        out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+
+        fs::rename(tmp, path)
+    }
+
+    /// Returns the cached type of `path`, or detects it and remembers the result.
+    pub fn detect(&mut self, path: &Path) -> io::Result<FileType> {
+        self.analyze(path).map(FileType::from)
+    }
+
+    /// [`DetectionCache::detect`] with the status and detail of [`Detector::analyze_file`].
+    pub fn analyze(&mut self, path: &Path) -> io::Result<DetectionResult> {
+        let detector = self
+            .detector
+            .as_ref()
+            .unwrap_or_else(|| FileFinch::detector());
+        if detector.profile() != self.profile {
+            self.entries.clear();
+            self.profile = detector.profile();
+        }
+
+        let fingerprint = Fingerprint::of(&fs::metadata(path)?);
+
+        if let (Some(fingerprint), Some((cached, result))) = (fingerprint, self.entries.get(path))
+            && fingerprint == *cached
+        {
+            self.hits += 1;
+            return Ok(*result);
+        }
+
+        self.misses += 1;
+        let result = detector.analyze_file(path)?;
+        if let Some(fingerprint) = fingerprint {
+            self.entries
+                .insert(path.to_path_buf(), (fingerprint, result));
+        }
+
+        Ok(result)
+    }
+
+    /// Drops entries for files that no longer exist.
+    pub fn prune(&mut self) {
+        self.entries.retain(|path, _| path.exists());
+    }
+
+    /// How many [`DetectionCache::detect`] and [`DetectionCache::analyze`] calls were answered
+    /// from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// How many [`DetectionCache::detect`] and [`DetectionCache::analyze`] calls had to read the
+    /// file.
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DetectionStatus;
+
+    #[test]
+    fn test_cache_round_trip_and_invalidation() {
+        let dir = std::env::temp_dir().join(format!("file_finch_cache_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let data = dir.join("data.bin");
+        let cache_file = dir.join("cache/detections.tsv");
+        fs::write(&data, b"PAR1").unwrap();
+
+        let mut cache = DetectionCache::load(&cache_file).unwrap();
+        assert_eq!(cache.detect(&data).unwrap(), FileType::Parquet);
+        assert_eq!((cache.hits(), cache.misses()), (0, 1));
+        cache.save(&cache_file).unwrap();
+
+        let mut cache = DetectionCache::load(&cache_file).unwrap();
+        assert_eq!(cache.detect(&data).unwrap(), FileType::Parquet);
+        assert_eq!((cache.hits(), cache.misses()), (1, 0));
+
+        // A different size invalidates the entry even if the mtime has not ticked over.
+        fs::write(&data, b"ARROW1\x00\x00").unwrap();
+        assert_eq!(cache.detect(&data).unwrap(), FileType::Arrow);
+        assert_eq!(cache.misses(), 1);
+
+        fs::write(&cache_file, "# file_finch 0.0.0\n4\t0\tCSV\t/tmp/a.csv\n").unwrap();
+        assert_eq!(DetectionCache::load(&cache_file).unwrap().entries.len(), 0);
+
+        let stale = format!(
+            "# file_finch {} detection 0\n4\t0\tCSV\t/tmp/a.csv\n",
+            env!("CARGO_PKG_VERSION")
+        );
+        fs::write(&cache_file, stale).unwrap();
+        assert_eq!(DetectionCache::load(&cache_file).unwrap().entries.len(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cache_keeps_status_and_detail() {
+        let dir =
+            std::env::temp_dir().join(format!("file_finch_cache_status_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let cache_file = dir.join("detections.tsv");
+        let files = [
+            (dir.join("pending.csv"), Vec::new()),
+            (
+                dir.join("batches.arrows"),
+                FileType::Arrow.examples()[1].clone(),
+            ),
+        ];
+
+        let mut cache = DetectionCache::new();
+        let mut detected = Vec::new();
+        for (path, data) in &files {
+            fs::write(path, data).unwrap();
+            detected.push(cache.analyze(path).unwrap());
+        }
+        assert_eq!(detected[0].status, DetectionStatus::Empty);
+        assert!(detected[1].detail.is_some());
+        cache.save(&cache_file).unwrap();
+
+        let mut cache = DetectionCache::load(&cache_file).unwrap();
+        for ((path, _), expected) in files.iter().zip(detected) {
+            assert_eq!(cache.analyze(path).unwrap(), expected);
+        }
+        assert_eq!(cache.misses(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cache_keeps_results_per_profile() {
+        let dir =
+            std::env::temp_dir().join(format!("file_finch_cache_profile_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let data = dir.join("sites.tsv");
+        let cache_file = dir.join("detections.tsv");
+        fs::write(&data, b"id\tname\n1\talpha\n2\tbeta\n").unwrap();
+        let data_lake = || Detector::new().with_profile(Profile::DataLake);
+
+        let mut cache = DetectionCache::load(&cache_file)
+            .unwrap()
+            .with_detector(data_lake());
+        assert_eq!(cache.detect(&data).unwrap(), FileType::Csv);
+        cache.save(&cache_file).unwrap();
+
+        let mut cache = DetectionCache::load(&cache_file)
+            .unwrap()
+            .with_detector(data_lake());
+        assert_eq!(cache.detect(&data).unwrap(), FileType::Csv);
+        assert_eq!((cache.hits(), cache.misses()), (1, 0));
+
+        // The default profile does not take tabs, so the cached CSV must not be reused.
+        let mut cache = DetectionCache::load(&cache_file).unwrap();
+        assert_eq!(cache.detect(&data).unwrap(), FileType::Unknown);
+        assert_eq!((cache.hits(), cache.misses()), (0, 1));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_skips_unwritable_paths() {
+        let dir =
+            std::env::temp_dir().join(format!("file_finch_cache_paths_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let cache_file = dir.join("detections.tsv");
+
+        let mut cache = DetectionCache::new();
+        for name in ["plain.csv", "tab\there.csv", "line\nbreak.csv"] {
+            let data = dir.join(name);
+            fs::write(&data, b"PAR1").unwrap();
+            cache.detect(&data).unwrap();
+        }
+        cache.save(&cache_file).unwrap();
+
+        let loaded = DetectionCache::load(&cache_file).unwrap();
+        assert_eq!(
+            loaded.entries.keys().collect::<Vec<_>>(),
+            vec![&dir.join("plain.csv")]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
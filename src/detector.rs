@@ -0,0 +1,841 @@
+use crate::{
+    Detail, DetectionResult, DetectionStatus, FileType, InspectOptions, Inspection, archive, arrow,
+    language, temporal,
+};
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::path::Path;
+
+const EXCEL_PATTERNS: &[&[u8]] = &[
+    b"xl/worksheets",
+    b"xl/_rels",
+    b"docProps/",
+    b"[Content_Types]",
+    b"xl/workbook",
+    b"xl/styles",
+    b"xl/theme",
+    b"xl/strings",
+    b"xl/charts",
+    b"xl/drawings",
+    b"xl/sharedStrings",
+    b"xl/metadata",
+    b"xl/calc",
+];
+
+const SHAPEFILE_PATTERNS: &[&[u8]] = &[b".shp", b".dbf", b".prj", b".shx"];
+
+/// ZIP entry file names that together mark a bundle of files following a known convention,
+/// checked before the single-format patterns above. Add new conventions here.
+const BUNDLE_SIGNATURES: &[BundleSignature] = &[BundleSignature {
+    file_type: FileType::Gtfs,
+    // stop_times.txt is usually the biggest entry, so anything stored after it is often beyond the
+    // prefix. Two of the required files are enough.
+    entries: &[
+        b"agency.txt",
+        b"calendar.txt",
+        b"routes.txt",
+        b"stop_times.txt",
+        b"trips.txt",
+    ],
+    min_matches: 2,
+}];
+
+/// Bumped whenever a change to detection can give a different answer for the same bytes, so
+/// stored results (see [`crate::cache::DetectionCache`]) are thrown away.
+pub(crate) const DETECTION_REVISION: u32 = 3;
+
+/// The shortest input detection is attempted on, the length of the shortest magic number.
+pub const MIN_DETECTABLE_LEN: usize = 4;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// How much of a text file is looked at when deciding whether it is CSV.
+const CSV_SAMPLE_BYTES: usize = 1000;
+const CSV_SAMPLE_LINES: usize = 5;
+
+/// How far into a file the first HTML tag is looked for, past any declaration and comments.
+const HTML_SAMPLE_BYTES: usize = 1024;
+/// What an HTML document's first tag starts with. Each has to be followed by `>`, `/` or
+/// whitespace, so `<header>` or `<titles>` do not count.
+const HTML_OPENINGS: &[&[u8]] = &[b"<!doctype html", b"<html", b"<head", b"<body", b"<title"];
+
+/// Named tunings of [`Detector`] for common deployments, applied with [`Detector::with_profile`].
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub enum Profile {
+    /// Balanced settings, what [`Detector::new`] uses.
+    #[default]
+    Default,
+    /// Untrusted uploads. HTML is detected, the only profile that reports it: a document opening
+    /// with an HTML doctype or `<html>`, `<head>`, `<body>` or `<title>` tag is an error page saved
+    /// in place of the file, and this is checked before other text formats. The length-prefixed
+    /// Arrow stream heuristic, which can fire on arbitrary binary, is off.
+    WebUpload,
+    /// Bulk exports. Tab, pipe and semicolon separated text counts as CSV, and more lines have to
+    /// agree before it does. A row cut short by the sample size is left out of the count.
+    DataLake,
+    /// Desktop GIS folders. Semicolon separated CSV, common in European locales, is accepted and
+    /// Arrow streams, which have no magic number, are not detected, so binary sidecar files are
+    /// never mistaken for them. Arrow files starting with `ARROW1` still are.
+    GisDesktop,
+}
+
+impl Profile {
+    pub(crate) const ALL: [Profile; 4] = [
+        Profile::Default,
+        Profile::WebUpload,
+        Profile::DataLake,
+        Profile::GisDesktop,
+    ];
+
+    fn tuning(self) -> Tuning {
+        let delimiters: &[u8] = match self {
+            Profile::Default | Profile::WebUpload => b",",
+            Profile::DataLake => b",\t|;",
+            Profile::GisDesktop => b",;",
+        };
+
+        Tuning {
+            delimiters: DelimiterSet::new(delimiters),
+            csv_sample_lines: match self {
+                Profile::DataLake => 20,
+                _ => CSV_SAMPLE_LINES,
+            },
+            whole_lines: self == Profile::DataLake,
+            html: self == Profile::WebUpload,
+            arrow_streams: self != Profile::GisDesktop,
+            legacy_arrow_stream: matches!(self, Profile::Default | Profile::DataLake),
+        }
+    }
+}
+
+/// The knobs a [`Profile`] sets.
+#[derive(Debug, Clone)]
+struct Tuning {
+    delimiters: DelimiterSet,
+    csv_sample_lines: usize,
+    /// Leave out a last line cut short by [`CSV_SAMPLE_BYTES`], which the longer samples of
+    /// [`Profile::DataLake`] usually end on.
+    whole_lines: bool,
+    /// Detect HTML pages, ahead of the other text formats. Off by default so callers matching on
+    /// the types `Detector` returned before [`FileType::Html`] existed see no change.
+    html: bool,
+    /// Detect Arrow IPC streams from their message framing.
+    arrow_streams: bool,
+    /// Accept streams without the continuation marker on the strength of their length prefixes.
+    legacy_arrow_stream: bool,
+}
+
+/// A bundle is recognised once `min_matches` of its entries, compared by file name ignoring any
+/// directory, have local headers in the ZIP prefix.
+struct BundleSignature {
+    file_type: FileType,
+    entries: &'static [&'static [u8]],
+    min_matches: usize,
+}
+
+/// Byte patterns tagged with the type they point to, indexed by first byte so a buffer can be
+/// checked against all of them in a single pass.
+#[derive(Debug, Clone)]
+struct PatternSet {
+    patterns: Vec<(&'static [u8], FileType)>,
+    by_first_byte: Vec<Vec<usize>>,
+}
+
+impl PatternSet {
+    fn new(patterns: Vec<(&'static [u8], FileType)>) -> Self {
+        let mut by_first_byte = vec![Vec::new(); 256];
+        for (index, (pattern, _)) in patterns.iter().enumerate() {
+            if let Some(&first) = pattern.first() {
+                by_first_byte[first as usize].push(index);
+            }
+        }
+
+        PatternSet {
+            patterns,
+            by_first_byte,
+        }
+    }
+
+    /// Every type with at least one pattern somewhere in `bytes`, in pattern order.
+    fn matches(&self, bytes: &[u8]) -> Vec<FileType> {
+        let mut found = vec![false; self.patterns.len()];
+
+        for (pos, &byte) in bytes.iter().enumerate() {
+            for &index in &self.by_first_byte[byte as usize] {
+                if !found[index] && bytes[pos..].starts_with(self.patterns[index].0) {
+                    found[index] = true;
+                }
+            }
+        }
+
+        let mut types: Vec<FileType> = Vec::new();
+        for (index, (_, file_type)) in self.patterns.iter().enumerate() {
+            if found[index] && !types.contains(file_type) {
+                types.push(*file_type);
+            }
+        }
+        types
+    }
+
+    /// How many distinct patterns of each type are exactly equal to one of `candidates`.
+    fn exact_counts(&self, candidates: &[&[u8]]) -> HashMap<FileType, usize> {
+        let mut found = vec![false; self.patterns.len()];
+
+        for candidate in candidates {
+            let Some(&first) = candidate.first() else {
+                continue;
+            };
+            for &index in &self.by_first_byte[first as usize] {
+                if self.patterns[index].0 == *candidate {
+                    found[index] = true;
+                }
+            }
+        }
+
+        let mut counts = HashMap::new();
+        for (index, (_, file_type)) in self.patterns.iter().enumerate() {
+            if found[index] {
+                *counts.entry(*file_type).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+}
+
+/// The bytes treated as CSV field separators.
+#[derive(Debug, Clone)]
+struct DelimiterSet {
+    delimiters: Vec<u8>,
+}
+
+impl DelimiterSet {
+    fn new(delimiters: &[u8]) -> Self {
+        DelimiterSet {
+            delimiters: delimiters.to_vec(),
+        }
+    }
+
+    /// The first delimiter that every sampled line has the same, non-zero, count of.
+    fn consistent(&self, lines: &[&str]) -> Option<u8> {
+        self.delimiters.iter().copied().find(|&delimiter| {
+            let mut counts = lines.iter().map(|line| occurrences(line, delimiter));
+            match counts.next() {
+                Some(first) => first > 0 && counts.all(|count| count == first),
+                None => false,
+            }
+        })
+    }
+}
+
+fn occurrences(line: &str, delimiter: u8) -> usize {
+    line.bytes().filter(|&b| b == delimiter).count()
+}
+
+/// A reusable file type detector.
+///
+/// Pattern tables are built once in [`Detector::new`], so keep one around (it is `Send + Sync`)
+/// rather than building one per call. [`crate::FileFinch`] uses a shared default instance.
+#[derive(Debug, Clone)]
+pub struct Detector {
+    zip_patterns: PatternSet,
+    bundle_entries: PatternSet,
+    profile: Profile,
+    tuning: Tuning,
+}
+
+impl Default for Detector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Detector {
+    pub fn new() -> Self {
+        let zip_patterns = EXCEL_PATTERNS
+            .iter()
+            .map(|&pattern| (pattern, FileType::Excel))
+            .chain(
+                SHAPEFILE_PATTERNS
+                    .iter()
+                    .map(|&pattern| (pattern, FileType::Shapefile)),
+            )
+            .collect();
+
+        let bundle_entries = BUNDLE_SIGNATURES
+            .iter()
+            .flat_map(|bundle| {
+                bundle
+                    .entries
+                    .iter()
+                    .map(|&entry| (entry, bundle.file_type))
+            })
+            .collect();
+
+        Detector {
+            zip_patterns: PatternSet::new(zip_patterns),
+            bundle_entries: PatternSet::new(bundle_entries),
+            profile: Profile::Default,
+            tuning: Profile::Default.tuning(),
+        }
+    }
+
+    /// Replaces the current tuning with that of `profile`.
+    pub fn with_profile(mut self, profile: Profile) -> Self {
+        self.profile = profile;
+        self.tuning = profile.tuning();
+        self
+    }
+
+    /// The profile last applied, [`Profile::Default`] for a new detector.
+    pub fn profile(&self) -> Profile {
+        self.profile
+    }
+
+    pub fn detect(&self, bytes: &[u8]) -> FileType {
+        self.analyze(bytes).into()
+    }
+
+    /// Like [`Detector::detect`], but falls back to the file extension when the content alone is
+    /// not conclusive.
+    pub fn detect_from_path(&self, path: &str, bytes: &[u8]) -> FileType {
+        self.analyze_from_path(path, bytes).into()
+    }
+
+    /// Detects the type and says why nothing was found when the answer is `Unknown`: empty and
+    /// whitespace-only input is [`DetectionStatus::Empty`] and anything shorter than
+    /// [`MIN_DETECTABLE_LEN`] is [`DetectionStatus::TooSmall`], neither is run through detection.
+    pub fn analyze(&self, bytes: &[u8]) -> DetectionResult {
+        if let Some(status) = Self::unusable(bytes) {
+            return DetectionResult::unknown(status);
+        }
+
+        let file_type = self.detect_content(bytes);
+        DetectionResult::from_file_type(file_type).with_detail(Self::detail(file_type, bytes))
+    }
+
+    /// [`Detector::analyze`] with the extension fallback of [`Detector::detect_from_path`]. The
+    /// extension is not consulted for empty or too small input.
+    pub fn analyze_from_path(&self, path: &str, bytes: &[u8]) -> DetectionResult {
+        if let Some(status) = Self::unusable(bytes) {
+            return DetectionResult::unknown(status);
+        }
+
+        let detected = self.detect_content(bytes);
+        if detected != FileType::Unknown {
+            return DetectionResult::from_file_type(detected)
+                .with_detail(Self::detail(detected, bytes));
+        }
+
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase);
+        let fallback = match extension.as_deref() {
+            Some("csv") => FileType::Csv,
+            Some("json" | "geojson") if Self::looks_like_geojson(bytes) => FileType::Geojson,
+            _ => FileType::Unknown,
+        };
+
+        DetectionResult::from_file_type(fallback)
+    }
+
+    /// Detects a file on disk from its first [`archive::DEFAULT_PREFIX_LEN`] bytes and its name.
+    pub fn detect_file(&self, path: &Path) -> io::Result<FileType> {
+        self.analyze_file(path).map(FileType::from)
+    }
+
+    /// [`Detector::detect_file`] with the status and detail of [`Detector::analyze_from_path`].
+    pub fn analyze_file(&self, path: &Path) -> io::Result<DetectionResult> {
+        let mut prefix = Vec::new();
+        std::fs::File::open(path)?
+            .take(archive::DEFAULT_PREFIX_LEN as u64)
+            .read_to_end(&mut prefix)?;
+
+        Ok(self.analyze_from_path(&path.to_string_lossy(), &prefix))
+    }
+
+    pub fn inspect(&self, bytes: &[u8], options: &InspectOptions) -> Inspection {
+        let DetectionResult {
+            file_type,
+            status,
+            detail,
+        } = self.analyze(bytes);
+
+        let delimiter = match file_type {
+            FileType::Csv => self.csv_delimiter(bytes).unwrap_or(b','),
+            _ => b',',
+        };
+
+        let temporal_extent = if options.temporal_extent {
+            temporal::sniff(file_type, bytes, delimiter)
+        } else {
+            None
+        };
+
+        let language = if options.language {
+            language::sniff(file_type, bytes, delimiter)
+        } else {
+            None
+        };
+
+        Inspection {
+            file_type,
+            status,
+            detail,
+            temporal_extent,
+            language,
+        }
+    }
+
+    fn unusable(bytes: &[u8]) -> Option<DetectionStatus> {
+        let content = bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes);
+
+        if content.iter().all(u8::is_ascii_whitespace) {
+            Some(DetectionStatus::Empty)
+        } else if bytes.len() < MIN_DETECTABLE_LEN {
+            Some(DetectionStatus::TooSmall)
+        } else {
+            None
+        }
+    }
+
+    /// Format specific facts worth passing on. For Arrow that is the body compression of the first
+    /// batch header in the prefix, which is there even when the bodies themselves are cut off.
+    fn detail(file_type: FileType, bytes: &[u8]) -> Option<Detail> {
+        match file_type {
+            FileType::Arrow => arrow::body_compression(bytes).map(Detail::ArrowBodyCompression),
+            _ => None,
+        }
+    }
+
+    fn detect_content(&self, bytes: &[u8]) -> FileType {
+        if let Some(file_type) = self.detect_by_magic(bytes) {
+            return file_type;
+        }
+
+        if self.tuning.html && Self::looks_like_html(bytes) {
+            return FileType::Html;
+        }
+
+        if Self::looks_like_geojson(bytes) {
+            return FileType::Geojson;
+        }
+
+        if self.csv_delimiter(bytes).is_some() {
+            return FileType::Csv;
+        }
+
+        FileType::Unknown
+    }
+
+    fn detect_by_magic(&self, bytes: &[u8]) -> Option<FileType> {
+        match bytes {
+            [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some(FileType::Png),
+            [0x50, 0x4B, 0x03, 0x04, ..] => self.detect_zip_content(bytes),
+            [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1, ..] => Some(FileType::Excel),
+            [0x50, 0x41, 0x52, 0x31, ..] => Some(FileType::Parquet),
+            bytes if bytes.starts_with(b"SQLite format 3\x00") => Some(FileType::Geopackage),
+            bytes if bytes.starts_with(b"ARROW1") => Some(FileType::Arrow),
+            bytes if self.tuning.arrow_streams && arrow::is_stream(bytes) => Some(FileType::Arrow),
+            bytes
+                if self.tuning.arrow_streams
+                    && self.tuning.legacy_arrow_stream
+                    && Self::is_arrow_ipc_stream(bytes) =>
+            {
+                Some(FileType::Arrow)
+            }
+            _ => None,
+        }
+    }
+
+    fn detect_zip_content(&self, bytes: &[u8]) -> Option<FileType> {
+        let file_names: Vec<&[u8]> = archive::entry_names(bytes)
+            .into_iter()
+            .map(|name| name.rsplit(|&b| b == b'/').next().unwrap_or(name))
+            .collect();
+        let counts = self.bundle_entries.exact_counts(&file_names);
+        if let Some(bundle) = BUNDLE_SIGNATURES.iter().find(|bundle| {
+            counts.get(&bundle.file_type).copied().unwrap_or(0) >= bundle.min_matches
+        }) {
+            return Some(bundle.file_type);
+        }
+
+        match self.zip_patterns.matches(bytes).as_slice() {
+            [file_type] => Some(*file_type),
+            _ => None,
+        }
+    }
+
+    fn looks_like_geojson(bytes: &[u8]) -> bool {
+        let Ok(text) = std::str::from_utf8(bytes) else {
+            return false;
+        };
+        let text_lower = text.trim_start().to_lowercase();
+
+        text_lower.starts_with("{")
+            && text_lower.contains(r#""type""#)
+            && (text_lower.contains(r#""featurecollection""#)
+                || text_lower.contains(r#""feature""#)
+                || text_lower.contains(r#""geometry""#))
+    }
+
+    /// The delimiter `bytes` is consistently separated by, if it reads as CSV.
+    fn csv_delimiter(&self, bytes: &[u8]) -> Option<u8> {
+        let text = std::str::from_utf8(bytes).ok()?;
+
+        let mut end = text.len().min(CSV_SAMPLE_BYTES);
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        let sample = &text[..end];
+        let mut lines: Vec<&str> = sample.lines().collect();
+        let cut_short = end < text.len() && !sample.ends_with('\n');
+        if self.tuning.whole_lines
+            && cut_short
+            && (3..=self.tuning.csv_sample_lines).contains(&lines.len())
+        {
+            // The last line is a row cut off mid-way and would fail the count. It is left out as
+            // long as the whole lines agree and it has no more delimiters than they do.
+            let partial = lines.pop().unwrap_or_default();
+            if let Some(delimiter) = self.tuning.delimiters.consistent(&lines)
+                && occurrences(partial, delimiter) <= occurrences(lines[0], delimiter)
+            {
+                return Some(delimiter);
+            }
+            lines.push(partial);
+        }
+        lines.truncate(self.tuning.csv_sample_lines);
+        self.tuning.delimiters.consistent(&lines)
+    }
+
+    /// A document whose first tag, after any XML declaration and comments, is one of
+    /// [`HTML_OPENINGS`]. Text that merely mentions a tag further in (a CSV cell, a GeoJSON popup)
+    /// and XML formats with their own `<title>` (KML, GPX, Atom, SVG) do not qualify.
+    fn looks_like_html(bytes: &[u8]) -> bool {
+        let content = bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes);
+        let sample = content[..content.len().min(HTML_SAMPLE_BYTES)].to_ascii_lowercase();
+
+        let mut rest = sample.trim_ascii_start();
+        loop {
+            let end = if rest.starts_with(b"<?") {
+                find(rest, b"?>").map(|i| i + 2)
+            } else if rest.starts_with(b"<!--") {
+                find(rest, b"-->").map(|i| i + 3)
+            } else {
+                break;
+            };
+            let Some(end) = end else {
+                return false;
+            };
+            rest = rest[end..].trim_ascii_start();
+        }
+
+        HTML_OPENINGS.iter().any(|opening| {
+            rest.strip_prefix(*opening).is_some_and(|after| {
+                after
+                    .first()
+                    .is_none_or(|&b| b == b'>' || b == b'/' || b.is_ascii_whitespace())
+            })
+        })
+    }
+
+    fn is_arrow_ipc_stream(bytes: &[u8]) -> bool {
+        if bytes.len() < 8 {
+            return false;
+        }
+
+        let continuation = [0xFF, 0xFF, 0xFF, 0xFF];
+        if bytes[4..8] == continuation && bytes[0..4] == [0x00, 0x00, 0x00, 0x00] {
+            return true;
+        }
+
+        let message_length = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let metadata_length = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+
+        (8..0x100000).contains(&message_length)
+            && metadata_length > 0
+            && metadata_length < message_length
+            && (message_length as usize) <= bytes.len()
+            && bytes.len() > 8
+            && !bytes[8..].starts_with(b"{")
+            && !bytes[8..].starts_with(b"\"")
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_pattern_set_matches() {
+        let set = PatternSet::new(vec![
+            (b"xl/workbook", FileType::Excel),
+            (b".shp", FileType::Shapefile),
+            (b"xl/styles", FileType::Excel),
+        ]);
+
+        assert_eq!(
+            set.matches(b"..xl/styles..xl/workbook"),
+            vec![FileType::Excel]
+        );
+        assert_eq!(
+            set.matches(b"a.shp xl/workbook"),
+            vec![FileType::Excel, FileType::Shapefile]
+        );
+        assert!(set.matches(b"nothing here").is_empty());
+    }
+
+    #[test]
+    fn test_csv_sample_respects_char_boundaries() {
+        // The 1000 byte sample boundary falls inside the "é".
+        let mut text = "name,town\n".repeat(99);
+        text.push_str("abcdefghié,x\n");
+        assert_eq!(Detector::new().detect(text.as_bytes()), FileType::Csv);
+    }
+
+    #[test]
+    fn test_analyze_empty_and_small_inputs() {
+        let detector = Detector::new();
+
+        for empty in [&b""[..], b" \r\n\t ", b"\xEF\xBB\xBF\n"] {
+            let result = detector.analyze(empty);
+            assert_eq!(result.status, DetectionStatus::Empty);
+            assert_eq!(result.file_type, FileType::Unknown);
+        }
+
+        assert_eq!(detector.analyze(b"a,b").status, DetectionStatus::TooSmall);
+        assert_eq!(detector.analyze(b"PAR").status, DetectionStatus::TooSmall);
+        assert_eq!(detector.analyze(b"PAR1").status, DetectionStatus::Detected);
+        assert_eq!(
+            detector.analyze(b"\x12\x34\x56\x78").status,
+            DetectionStatus::Unrecognised
+        );
+    }
+
+    #[test]
+    fn test_analyze_from_path_ignores_extension_when_empty() {
+        let detector = Detector::new();
+
+        assert_eq!(
+            detector.analyze_from_path("a.csv", b"").status,
+            DetectionStatus::Empty
+        );
+        assert_eq!(
+            detector.detect_from_path("a.csv", b"  \n"),
+            FileType::Unknown
+        );
+        assert_eq!(
+            detector.detect_from_path("a.csv", b"just some text"),
+            FileType::Csv
+        );
+    }
+
+    #[test]
+    fn test_detect_gtfs_bundle() {
+        let detector = Detector::new();
+
+        let gtfs = examples::zip(&[
+            ("feed/agency.txt", b"agency_id,agency_name\nA,Example\n"),
+            ("feed/routes.txt", b"route_id,route_type\nR,3\n"),
+        ]);
+        assert_eq!(detector.detect(&gtfs), FileType::Gtfs);
+
+        // Names in file contents, or as part of longer names, are not entries.
+        let shapefile = examples::zip(&[
+            (
+                "README.txt",
+                b"Built from routes.txt and trips.txt of the bus feed.",
+            ),
+            ("wards.shp", b""),
+        ]);
+        assert_eq!(detector.detect(&shapefile), FileType::Shapefile);
+
+        let csvs = examples::zip(&[
+            ("bus_routes.txt", b"route_id\nR\n"),
+            ("school_trips.txt", b"trip_id\nT\n"),
+        ]);
+        assert_eq!(detector.detect(&csvs), FileType::Unknown);
+    }
+
+    #[test]
+    fn test_web_upload_profile() {
+        let error_page = b"<head><title>404 Not Found</title></head>\n<body>nginx</body>";
+        let legacy_stream = b"\x10\x00\x00\x00\x08\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
+
+        let detector = Detector::new();
+        assert_eq!(detector.detect(error_page), FileType::Unknown);
+        assert_eq!(
+            detector.detect(b"<!DOCTYPE html>\n<html>"),
+            FileType::Unknown
+        );
+        assert_eq!(detector.detect(legacy_stream), FileType::Arrow);
+
+        let detector = Detector::new().with_profile(Profile::WebUpload);
+        assert_eq!(detector.detect(error_page), FileType::Html);
+        assert_eq!(detector.detect(legacy_stream), FileType::Unknown);
+        assert_eq!(detector.detect(b"ARROW1\x00\x00"), FileType::Arrow);
+
+        let pages: [&[u8]; 3] = [
+            b"\xEF\xBB\xBF  <!doctype HTML PUBLIC \"-//W3C//DTD HTML 4.01//EN\">\n<html>",
+            b"<!-- served by nginx -->\n<html>\r\n<head><title>502 Bad Gateway</title>",
+            b"<?xml version=\"1.0\"?>\n<html xmlns=\"http://www.w3.org/1999/xhtml\">",
+        ];
+        for page in pages {
+            assert_eq!(detector.detect(page), FileType::Html);
+        }
+    }
+
+    #[test]
+    fn test_web_upload_leaves_html_mentions_alone() {
+        let detector = Detector::new().with_profile(Profile::WebUpload);
+
+        let csv = b"section,markup\nmasthead,<header>Welcome</header>\nfooter,<footer/>\n";
+        assert_eq!(detector.detect(csv), FileType::Csv);
+
+        let geojson = br#"{"type":"FeatureCollection","features":[{"type":"Feature",
+            "properties":{"popup":"<head><title>Site</title></head><body>Info</body>"},
+            "geometry":{"type":"Point","coordinates":[0,51]}}]}"#;
+        assert_eq!(detector.detect(geojson), FileType::Geojson);
+
+        let xml_with_titles: [&[u8]; 4] = [
+            b"<?xml version=\"1.0\"?>\n<kml><Document><title>Sites</title></Document></kml>",
+            b"<?xml version=\"1.0\"?>\n<gpx version=\"1.1\"><metadata><title>Run</title>",
+            b"<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed><title>Updates</title></feed>",
+            b"<svg xmlns=\"http://www.w3.org/2000/svg\"><title>Logo</title></svg>",
+        ];
+        for xml in xml_with_titles {
+            assert_eq!(detector.detect(xml), FileType::Unknown);
+        }
+        assert_eq!(
+            detector.detect(b"<headline>Not a page</headline>"),
+            FileType::Unknown
+        );
+    }
+
+    #[test]
+    fn test_delimiter_profiles() {
+        let tsv = b"id\tname\n1\talpha\n2\tbeta\n";
+        let semicolons = b"id;name\n1;alpha\n2;beta\n";
+
+        assert_eq!(Detector::new().detect(tsv), FileType::Unknown);
+        assert_eq!(
+            Detector::new().with_profile(Profile::DataLake).detect(tsv),
+            FileType::Csv
+        );
+
+        let detector = Detector::new().with_profile(Profile::GisDesktop);
+        assert_eq!(detector.detect(semicolons), FileType::Csv);
+        assert_eq!(detector.detect(tsv), FileType::Unknown);
+        assert_eq!(detector.detect(b"ARROW1\x00\x00"), FileType::Arrow);
+        for stream in FileType::Arrow.examples() {
+            assert_eq!(detector.detect(&stream), FileType::Unknown);
+        }
+
+        // Profiles replace each other rather than stacking.
+        let detector = detector.with_profile(Profile::Default);
+        assert_eq!(detector.detect(semicolons), FileType::Unknown);
+        assert_eq!(
+            detector.detect(&FileType::Arrow.examples()[0]),
+            FileType::Arrow
+        );
+    }
+
+    #[test]
+    fn test_inspect_splits_on_detected_delimiter() {
+        let tsv = "gorsaf\tdyddiad\tdisgrifiad\n\
+                   Caerdydd\t2022-04-01\tPrifddinas Cymru a'r ddinas fwyaf yn y wlad\n\
+                   Abertawe\t2023-09-30\tDinas ar arfordir y de a'r ail ddinas fwyaf\n";
+        let options = InspectOptions {
+            temporal_extent: true,
+            language: true,
+        };
+
+        let inspection = Detector::new()
+            .with_profile(Profile::DataLake)
+            .inspect(tsv.as_bytes(), &options);
+        assert_eq!(inspection.file_type, FileType::Csv);
+
+        let extent = inspection.temporal_extent.unwrap();
+        assert_eq!(extent.start.to_string(), "2022-04-01T00:00:00");
+        assert_eq!(extent.columns, vec!["dyddiad"]);
+        assert_eq!(
+            inspection.language.unwrap().language,
+            crate::language::Language::Welsh
+        );
+    }
+
+    #[test]
+    fn test_data_lake_samples_whole_lines() {
+        let detector = Detector::new().with_profile(Profile::DataLake);
+
+        for delimiter in [",", "\t"] {
+            let mut text = ["id", "station", "observed_at", "reading", "unit"].join(delimiter);
+            for row in 0..40 {
+                let fields = [
+                    format!("{:06}", row),
+                    format!("Station number {}", row),
+                    format!("2024-03-{:02}T09:30:00Z", row % 28 + 1),
+                    format!("{}.25", row * 3),
+                    "mm".to_string(),
+                ];
+                text.push('\n');
+                text.push_str(&fields.join(delimiter));
+            }
+
+            assert!(text.len() > CSV_SAMPLE_BYTES);
+            assert_eq!(detector.detect(text.as_bytes()), FileType::Csv);
+        }
+
+        // The cut line is only left out after two whole lines, and never when it has more
+        // delimiters than they do. The default profile always counts it, as it always has.
+        let long_line = format!("a,b\na,b\n{}", "x".repeat(1200));
+        let too_few = format!("a,b\n{}", "x".repeat(1200));
+        let too_many = format!("a,b\na,b\n{}", "x,".repeat(600));
+        assert_eq!(detector.detect(long_line.as_bytes()), FileType::Csv);
+        assert_eq!(detector.detect(too_few.as_bytes()), FileType::Unknown);
+        assert_eq!(detector.detect(too_many.as_bytes()), FileType::Unknown);
+        assert_eq!(
+            Detector::new().detect(long_line.as_bytes()),
+            FileType::Unknown
+        );
+    }
+
+    #[test]
+    fn test_arrow_body_compression_detail() {
+        let detector = Detector::new();
+
+        let zstd = Some(arrow::ArrowCompression::Zstd);
+        let result = detector.analyze(&examples::truncated_arrow_stream(zstd));
+        assert_eq!(result.file_type, FileType::Arrow);
+        assert_eq!(result.detail, zstd.map(Detail::ArrowBodyCompression));
+
+        let result = detector.analyze(&examples::truncated_arrow_stream(None));
+        assert_eq!(result.file_type, FileType::Arrow);
+        assert_eq!(result.detail, None);
+    }
+
+    #[test]
+    fn test_detector_shared_across_threads() {
+        let detector = Arc::new(Detector::new());
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let detector = Arc::clone(&detector);
+                std::thread::spawn(move || detector.detect(b"PAR1"))
+            })
+            .collect();
+
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), FileType::Parquet);
+        }
+    }
+}
@@ -0,0 +1,724 @@
+// Builds the smallest valid sample of each format, see FileType::examples. Tests take their ZIP,
+// Arrow IPC and Parquet bytes from here too.
+
+use crate::archive::{
+    CENTRAL_DIRECTORY_HEADER, DATA_DESCRIPTOR, END_OF_CENTRAL_DIRECTORY, FLAG_DATA_DESCRIPTOR,
+    LOCAL_FILE_HEADER, METHOD_DEFLATED, METHOD_STORED,
+};
+use crate::parquet::{TYPE_BINARY, TYPE_I32, TYPE_I64, TYPE_LIST, TYPE_STRUCT};
+use crate::{ArrowCompression, FileType};
+use flate2::Compression;
+use flate2::Crc;
+use flate2::write::{DeflateEncoder, ZlibEncoder};
+use std::io::Write;
+
+pub(crate) fn generate(file_type: FileType) -> Vec<Vec<u8>> {
+    match file_type {
+        FileType::Geopackage => vec![geopackage()],
+        FileType::Shapefile => vec![shapefile()],
+        FileType::Geojson => vec![br#"{"type":"FeatureCollection","features":[]}"#.to_vec()],
+        FileType::Excel => vec![xlsx()],
+        FileType::Csv => vec![b"name,value\nalpha,1\n".to_vec()],
+        FileType::Parquet => vec![parquet()],
+        FileType::Arrow => vec![
+            arrow_stream(None),
+            arrow_stream(Some(ArrowCompression::Zstd)),
+        ],
+        FileType::Png => vec![png()],
+        FileType::Gtfs => vec![gtfs()],
+        FileType::Html => vec![
+            b"<!DOCTYPE html>\n<html><head><title></title></head><body></body></html>\n".to_vec(),
+        ],
+        FileType::Unknown => Vec::new(),
+    }
+}
+
+const SQLITE_PAGE_SIZE: usize = 4096;
+
+const SPATIAL_REF_SYS_SQL: &str = "CREATE TABLE gpkg_spatial_ref_sys (srs_name TEXT NOT NULL, \
+    srs_id INTEGER NOT NULL PRIMARY KEY, organization TEXT NOT NULL, \
+    organization_coordsys_id INTEGER NOT NULL, definition TEXT NOT NULL, description TEXT)";
+
+const CONTENTS_SQL: &str = "CREATE TABLE gpkg_contents (table_name TEXT NOT NULL PRIMARY KEY, \
+    data_type TEXT NOT NULL, identifier TEXT UNIQUE, description TEXT DEFAULT '', \
+    last_change DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')), \
+    min_x DOUBLE, min_y DOUBLE, max_x DOUBLE, max_y DOUBLE, srs_id INTEGER, \
+    CONSTRAINT fk_gc_r_srs_id FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys(srs_id))";
+
+const WGS84_WKT: &str = concat!(
+    r#"GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563,"#,
+    r#"AUTHORITY["EPSG","7030"]],AUTHORITY["EPSG","6326"]],PRIMEM["Greenwich",0,"#,
+    r#"AUTHORITY["EPSG","8901"]],UNIT["degree",0.0174532925199433,"#,
+    r#"AUTHORITY["EPSG","9122"]],AUTHORITY["EPSG","4326"]]"#
+);
+
+enum SqlValue<'a> {
+    Null,
+    Int(i64),
+    Text(&'a str),
+}
+
+/// A GeoPackage 1.2 with no layers: `gpkg_spatial_ref_sys` holding the three required reference
+/// systems and an empty `gpkg_contents`, written page by page as SQLite would.
+fn geopackage() -> Vec<u8> {
+    use SqlValue::{Int, Null, Text};
+
+    // Page 1 is the schema, then one root page per table and per automatic index.
+    let schema = [
+        [
+            Text("table"),
+            Text("gpkg_spatial_ref_sys"),
+            Text("gpkg_spatial_ref_sys"),
+            Int(2),
+            Text(SPATIAL_REF_SYS_SQL),
+        ],
+        [
+            Text("table"),
+            Text("gpkg_contents"),
+            Text("gpkg_contents"),
+            Int(3),
+            Text(CONTENTS_SQL),
+        ],
+        [
+            Text("index"),
+            Text("sqlite_autoindex_gpkg_contents_1"),
+            Text("gpkg_contents"),
+            Int(4),
+            Null,
+        ],
+        [
+            Text("index"),
+            Text("sqlite_autoindex_gpkg_contents_2"),
+            Text("gpkg_contents"),
+            Int(5),
+            Null,
+        ],
+    ];
+    // srs_id is the rowid, which SQLite stores in place of the column as NULL.
+    let reference_systems = [
+        (
+            -1,
+            [
+                Text("Undefined cartesian SRS"),
+                Null,
+                Text("NONE"),
+                Int(-1),
+                Text("undefined"),
+                Text("undefined cartesian coordinate reference system"),
+            ],
+        ),
+        (
+            0,
+            [
+                Text("Undefined geographic SRS"),
+                Null,
+                Text("NONE"),
+                Int(0),
+                Text("undefined"),
+                Text("undefined geographic coordinate reference system"),
+            ],
+        ),
+        (
+            4326,
+            [
+                Text("WGS 84 geodetic"),
+                Null,
+                Text("EPSG"),
+                Int(4326),
+                Text(WGS84_WKT),
+                Text("longitude/latitude coordinates in decimal degrees on the WGS 84 spheroid"),
+            ],
+        ),
+    ];
+
+    let mut db = vec![0u8; 5 * SQLITE_PAGE_SIZE];
+    db[..16].copy_from_slice(b"SQLite format 3\x00");
+    db[16..18].copy_from_slice(&(SQLITE_PAGE_SIZE as u16).to_be_bytes());
+    // File format versions, reserved space and payload fractions.
+    db[18..24].copy_from_slice(&[1, 1, 0, 64, 32, 32]);
+    db[24..28].copy_from_slice(&1u32.to_be_bytes());
+    db[28..32].copy_from_slice(&5u32.to_be_bytes());
+    db[40..44].copy_from_slice(&1u32.to_be_bytes());
+    db[44..48].copy_from_slice(&4u32.to_be_bytes());
+    db[56..60].copy_from_slice(&1u32.to_be_bytes());
+    db[60..64].copy_from_slice(&10_200u32.to_be_bytes());
+    db[68..72].copy_from_slice(b"GPKG");
+    db[92..96].copy_from_slice(&1u32.to_be_bytes());
+    db[96..100].copy_from_slice(&3_045_000u32.to_be_bytes());
+
+    let mut pages = db.chunks_mut(SQLITE_PAGE_SIZE);
+    let rows: Vec<_> = (1..)
+        .zip(schema.iter().map(|row| sqlite_record(row)))
+        .collect();
+    table_leaf(pages.next().unwrap(), 100, &rows);
+    let rows: Vec<_> = reference_systems
+        .iter()
+        .map(|(rowid, row)| (*rowid, sqlite_record(row)))
+        .collect();
+    table_leaf(pages.next().unwrap(), 0, &rows);
+    table_leaf(pages.next().unwrap(), 0, &[]);
+
+    // Empty index b-tree leaves for the two UNIQUE columns.
+    for page in pages {
+        page[0] = 0x0A;
+        page[5..7].copy_from_slice(&(SQLITE_PAGE_SIZE as u16).to_be_bytes());
+    }
+    db
+}
+
+/// Writes a table b-tree leaf holding `rows` (in rowid order) into `page`, with the page header
+/// at `offset` since the first page starts with the database header.
+fn table_leaf(page: &mut [u8], offset: usize, rows: &[(i64, Vec<u8>)]) {
+    let mut content = SQLITE_PAGE_SIZE;
+    for (index, (rowid, record)) in rows.iter().enumerate() {
+        let mut cell = Vec::new();
+        sqlite_varint(&mut cell, record.len() as u64);
+        sqlite_varint(&mut cell, *rowid as u64);
+        cell.extend_from_slice(record);
+
+        content -= cell.len();
+        page[content..content + cell.len()].copy_from_slice(&cell);
+        let pointer = offset + 8 + 2 * index;
+        page[pointer..pointer + 2].copy_from_slice(&(content as u16).to_be_bytes());
+    }
+
+    page[offset] = 0x0D;
+    page[offset + 3..offset + 5].copy_from_slice(&(rows.len() as u16).to_be_bytes());
+    page[offset + 5..offset + 7].copy_from_slice(&(content as u16).to_be_bytes());
+}
+
+fn sqlite_record(values: &[SqlValue]) -> Vec<u8> {
+    let mut header = Vec::new();
+    let mut body = Vec::new();
+    for value in values {
+        match value {
+            SqlValue::Null => header.push(0),
+            SqlValue::Int(value) => {
+                header.push(6);
+                body.extend_from_slice(&value.to_be_bytes());
+            }
+            SqlValue::Text(text) => {
+                sqlite_varint(&mut header, 13 + 2 * text.len() as u64);
+                body.extend_from_slice(text.as_bytes());
+            }
+        }
+    }
+
+    // The header length counts itself, one byte for headers this short.
+    let mut record = vec![header.len() as u8 + 1];
+    record.extend_from_slice(&header);
+    record.extend_from_slice(&body);
+    record
+}
+
+/// SQLite's big-endian varint, whose ninth byte (only reached by negative numbers here) carries
+/// a full 8 bits.
+fn sqlite_varint(out: &mut Vec<u8>, value: u64) {
+    if value >> 56 != 0 {
+        let mut bytes = [0x80u8; 9];
+        bytes[8] = value as u8;
+        let mut rest = value >> 8;
+        for byte in bytes[..8].iter_mut().rev() {
+            *byte |= (rest & 0x7F) as u8;
+            rest >>= 7;
+        }
+        out.extend_from_slice(&bytes);
+        return;
+    }
+
+    let mut bytes = vec![(value & 0x7F) as u8];
+    let mut rest = value >> 7;
+    while rest != 0 {
+        bytes.push((rest & 0x7F) as u8 | 0x80);
+        rest >>= 7;
+    }
+    out.extend(bytes.iter().rev());
+}
+
+/// A zipped shapefile with no shapes: null-shape .shp and .shx headers and an empty .dbf.
+fn shapefile() -> Vec<u8> {
+    let mut header = vec![0u8; 100];
+    header[..4].copy_from_slice(&9994u32.to_be_bytes());
+    header[24..28].copy_from_slice(&50u32.to_be_bytes());
+    header[28..32].copy_from_slice(&1000u32.to_le_bytes());
+
+    // dBase III, no records, a header with no fields and a one byte (deletion flag) record.
+    let mut dbf = vec![0u8; 32];
+    dbf[..4].copy_from_slice(&[0x03, 126, 1, 1]);
+    dbf[8..10].copy_from_slice(&33u16.to_le_bytes());
+    dbf[10..12].copy_from_slice(&1u16.to_le_bytes());
+    dbf.extend_from_slice(&[0x0D, 0x1A]);
+
+    zip(&[
+        ("example.shp", &header),
+        ("example.shx", &header),
+        ("example.dbf", &dbf),
+    ])
+}
+
+fn xlsx() -> Vec<u8> {
+    const MAIN: &str = "http://schemas.openxmlformats.org/spreadsheetml/2006/main";
+    const RELATIONSHIPS: &str =
+        "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+    const PACKAGE_RELATIONSHIPS: &str =
+        "http://schemas.openxmlformats.org/package/2006/relationships";
+
+    let content_types = r#"<?xml version="1.0" encoding="UTF-8"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/></Types>"#;
+    let rels = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="{}"><Relationship Id="rId1" Type="{}/officeDocument" Target="xl/workbook.xml"/></Relationships>"#,
+        PACKAGE_RELATIONSHIPS, RELATIONSHIPS
+    );
+    let workbook = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><workbook xmlns="{}" xmlns:r="{}"><sheets><sheet name="Sheet1" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+        MAIN, RELATIONSHIPS
+    );
+    let workbook_rels = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="{}"><Relationship Id="rId1" Type="{}/worksheet" Target="worksheets/sheet1.xml"/></Relationships>"#,
+        PACKAGE_RELATIONSHIPS, RELATIONSHIPS
+    );
+    let sheet = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><worksheet xmlns="{}"><sheetData/></worksheet>"#,
+        MAIN
+    );
+
+    zip(&[
+        ("[Content_Types].xml", content_types.as_bytes()),
+        ("_rels/.rels", rels.as_bytes()),
+        ("xl/workbook.xml", workbook.as_bytes()),
+        ("xl/_rels/workbook.xml.rels", workbook_rels.as_bytes()),
+        ("xl/worksheets/sheet1.xml", sheet.as_bytes()),
+    ])
+}
+
+/// Thrift compact protocol writer, enough to build Parquet footers.
+#[derive(Default)]
+pub(crate) struct CompactWriter {
+    out: Vec<u8>,
+    last_ids: Vec<i16>,
+}
+
+impl CompactWriter {
+    pub fn field(&mut self, id: i16, ty: u8) -> &mut Self {
+        let last = self.last_ids.last_mut().expect("field outside struct");
+        let delta = id - std::mem::replace(last, id);
+        if (1..=15).contains(&delta) {
+            self.out.push(((delta as u8) << 4) | ty);
+        } else {
+            self.out.push(ty);
+            self.int(id as i64);
+        }
+        self
+    }
+
+    pub fn begin(&mut self) -> &mut Self {
+        self.last_ids.push(0);
+        self
+    }
+
+    pub fn end(&mut self) -> &mut Self {
+        self.out.push(0);
+        self.last_ids.pop();
+        self
+    }
+
+    pub fn int(&mut self, value: i64) -> &mut Self {
+        let mut zigzag = ((value << 1) ^ (value >> 63)) as u64;
+        loop {
+            let byte = (zigzag & 0x7F) as u8;
+            zigzag >>= 7;
+            if zigzag == 0 {
+                self.out.push(byte);
+                return self;
+            }
+            self.out.push(byte | 0x80);
+        }
+    }
+
+    pub fn binary(&mut self, value: &[u8]) -> &mut Self {
+        self.out.push(value.len() as u8);
+        self.out.extend_from_slice(value);
+        self
+    }
+
+    pub fn list(&mut self, len: u8, ty: u8) -> &mut Self {
+        self.out.push((len << 4) | ty);
+        self
+    }
+
+    /// The written FileMetaData as a Parquet file: magic, footer, footer length, magic.
+    pub fn into_parquet(self) -> Vec<u8> {
+        let mut bytes = b"PAR1".to_vec();
+        bytes.extend_from_slice(&self.out);
+        bytes.extend_from_slice(&(self.out.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(b"PAR1");
+        bytes
+    }
+}
+
+/// No row groups and a schema that is just the root, so no data pages are needed.
+fn parquet() -> Vec<u8> {
+    let mut w = CompactWriter::default();
+    w.begin();
+    w.field(1, TYPE_I32).int(1);
+    w.field(2, TYPE_LIST).list(1, TYPE_STRUCT);
+    w.begin().field(4, TYPE_BINARY).binary(b"schema");
+    w.field(5, TYPE_I32).int(0).end();
+    w.field(3, TYPE_I64).int(0);
+    w.field(4, TYPE_LIST).list(0, TYPE_STRUCT);
+    w.end();
+    w.into_parquet()
+}
+
+/// A two column Parquet file (`id`, `observed` as DATE) with one row group spanning the given
+/// day numbers. No column data, only what the footer needs.
+#[cfg(test)]
+pub(crate) fn parquet_with_date_stats(min_days: i32, max_days: i32) -> Vec<u8> {
+    let mut w = CompactWriter::default();
+    w.begin();
+    w.field(1, TYPE_I32).int(1);
+    w.field(2, TYPE_LIST).list(3, TYPE_STRUCT);
+    w.begin().field(4, TYPE_BINARY).binary(b"schema");
+    w.field(5, TYPE_I32).int(2).end();
+    w.begin().field(4, TYPE_BINARY).binary(b"id").end();
+    w.begin().field(4, TYPE_BINARY).binary(b"observed");
+    w.field(10, TYPE_STRUCT)
+        .begin()
+        .field(6, TYPE_STRUCT)
+        .begin()
+        .end()
+        .end();
+    w.end();
+    w.field(3, TYPE_I64).int(2);
+    w.field(4, TYPE_LIST).list(1, TYPE_STRUCT);
+    w.begin().field(1, TYPE_LIST).list(1, TYPE_STRUCT);
+    w.begin().field(3, TYPE_STRUCT).begin();
+    w.field(3, TYPE_LIST)
+        .list(1, TYPE_BINARY)
+        .binary(b"observed");
+    w.field(12, TYPE_STRUCT).begin();
+    w.field(5, TYPE_BINARY).binary(&max_days.to_le_bytes());
+    w.field(6, TYPE_BINARY).binary(&min_days.to_le_bytes());
+    w.end().end().end().end();
+    w.end();
+    w.into_parquet()
+}
+
+/// The header of an Arrow IPC message. Schemas have no fields and record batches no columns.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum IpcHeader {
+    Schema,
+    RecordBatch(Option<ArrowCompression>),
+}
+
+/// One IPC message (continuation marker, metadata length and flatbuffer metadata) announcing a
+/// body of `body_len` bytes. The body itself is left to the caller.
+pub(crate) fn ipc_message(header: IpcHeader, body_len: i64) -> Vec<u8> {
+    let mut message = vec![0u8; 64];
+    let mut put = |pos: usize, value: &[u8]| {
+        if message.len() < pos + value.len() {
+            message.resize(pos + value.len(), 0);
+        }
+        message[pos..pos + value.len()].copy_from_slice(value);
+    };
+
+    // Root offset, then the Message vtable (version, header_type, header, bodyLength) and table.
+    put(0, &16u32.to_le_bytes());
+    for (i, offset) in [12u16, 24, 4, 6, 8, 16].into_iter().enumerate() {
+        put(4 + 2 * i, &offset.to_le_bytes());
+    }
+    put(16, &12i32.to_le_bytes());
+    put(20, &4i16.to_le_bytes()); // MetadataVersion V5
+    put(32, &body_len.to_le_bytes());
+
+    match header {
+        IpcHeader::Schema => {
+            put(22, &[1]);
+            put(24, &24u32.to_le_bytes());
+
+            // Schema vtable with only `fields`, the table and the empty fields vector.
+            for (i, offset) in [8u16, 8, 0, 4].into_iter().enumerate() {
+                put(40 + 2 * i, &offset.to_le_bytes());
+            }
+            put(48, &8i32.to_le_bytes());
+            put(52, &4u32.to_le_bytes());
+        }
+        IpcHeader::RecordBatch(compression) => {
+            put(22, &[3]);
+            put(24, &28u32.to_le_bytes());
+
+            // RecordBatch vtable with `length` and, when compressed, `compression`, then its table.
+            let compression_offset = if compression.is_some() { 12 } else { 0 };
+            for (i, offset) in [12u16, 16, 4, 0, 0, compression_offset]
+                .into_iter()
+                .enumerate()
+            {
+                put(40 + 2 * i, &offset.to_le_bytes());
+            }
+            put(52, &12i32.to_le_bytes());
+
+            // BodyCompression vtable with only `codec`, and its table.
+            if let Some(compression) = compression {
+                put(64, &12u32.to_le_bytes());
+                for (i, offset) in [8u16, 8, 4, 0].into_iter().enumerate() {
+                    put(68 + 2 * i, &offset.to_le_bytes());
+                }
+                put(76, &8i32.to_le_bytes());
+                let codec = match compression {
+                    ArrowCompression::Lz4Frame => 0,
+                    ArrowCompression::Zstd => 1,
+                };
+                put(80, &[codec]);
+            }
+        }
+    }
+    message.resize(message.len().next_multiple_of(8), 0);
+
+    let mut bytes = vec![0xFF; 4];
+    bytes.extend_from_slice(&(message.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&message);
+    bytes
+}
+
+/// An IPC stream holding a schema with no fields, then the end-of-stream marker. With
+/// `compression` an empty record batch declaring it comes between the two.
+fn arrow_stream(compression: Option<ArrowCompression>) -> Vec<u8> {
+    let mut bytes = ipc_message(IpcHeader::Schema, 0);
+    if compression.is_some() {
+        bytes.extend_from_slice(&ipc_message(IpcHeader::RecordBatch(compression), 0));
+    }
+    bytes.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0]);
+    bytes
+}
+
+/// A schema message followed by one record batch whose body is cut off, as a prefix of a larger
+/// stream would be.
+#[cfg(test)]
+pub(crate) fn truncated_arrow_stream(compression: Option<ArrowCompression>) -> Vec<u8> {
+    let mut bytes = ipc_message(IpcHeader::Schema, 0);
+    bytes.extend_from_slice(&ipc_message(IpcHeader::RecordBatch(compression), 4096));
+    bytes.extend_from_slice(&[0u8; 64]);
+    bytes
+}
+
+/// A single black pixel, 8 bit greyscale.
+fn png() -> Vec<u8> {
+    let mut ihdr = Vec::new();
+    ihdr.extend_from_slice(&1u32.to_be_bytes());
+    ihdr.extend_from_slice(&1u32.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 0, 0, 0, 0]);
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(&[0, 0])
+        .expect("writing to a Vec cannot fail");
+    let idat = encoder.finish().expect("writing to a Vec cannot fail");
+
+    let mut bytes = b"\x89PNG\r\n\x1a\n".to_vec();
+    for (kind, data) in [(b"IHDR", &ihdr[..]), (b"IDAT", &idat), (b"IEND", &[])] {
+        bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(kind);
+        bytes.extend_from_slice(data);
+
+        let mut crc = Crc::new();
+        crc.update(kind);
+        crc.update(data);
+        bytes.extend_from_slice(&crc.sum().to_be_bytes());
+    }
+    bytes
+}
+
+/// The required GTFS tables, one agency, route, stop and trip each.
+fn gtfs() -> Vec<u8> {
+    zip(&[
+        (
+            "agency.txt",
+            b"agency_id,agency_name,agency_url,agency_timezone\nA,Example Transit,https://example.com,Europe/London\n",
+        ),
+        ("routes.txt", b"route_id,agency_id,route_short_name,route_type\nR,A,1,3\n"),
+        ("stops.txt", b"stop_id,stop_name,stop_lat,stop_lon\nS,Main Street,51.5,-0.1\n"),
+        ("trips.txt", b"route_id,service_id,trip_id\nR,WK,T\n"),
+        (
+            "stop_times.txt",
+            b"trip_id,arrival_time,departure_time,stop_id,stop_sequence\nT,08:00:00,08:00:00,S,1\n",
+        ),
+        (
+            "calendar.txt",
+            b"service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date\nWK,1,1,1,1,1,0,0,20260101,20261231\n",
+        ),
+    ])
+}
+
+/// One file in a generated ZIP, stored with its CRC and sizes in the local header by default.
+pub(crate) struct ZipEntry<'a> {
+    name: &'a str,
+    data: &'a [u8],
+    method: u16,
+    descriptor: bool,
+}
+
+impl<'a> ZipEntry<'a> {
+    pub fn new(name: &'a str, data: &'a [u8]) -> Self {
+        ZipEntry {
+            name,
+            data,
+            method: METHOD_STORED,
+            descriptor: false,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_deflate(mut self) -> Self {
+        self.method = METHOD_DEFLATED;
+        self
+    }
+
+    /// Labels the data, written as given, with some other compression method.
+    #[cfg(test)]
+    pub fn with_method(mut self, method: u16) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Zeroes the CRC and sizes in the local header and writes them to a signed data descriptor
+    /// after the data instead, as streaming writers do.
+    #[cfg(test)]
+    pub fn with_descriptor(mut self) -> Self {
+        self.descriptor = true;
+        self
+    }
+}
+
+/// A ZIP of stored entries with a central directory.
+pub(crate) fn zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let entries: Vec<_> = entries
+        .iter()
+        .map(|&(name, data)| ZipEntry::new(name, data))
+        .collect();
+    zip_entries(&entries)
+}
+
+pub(crate) fn zip_entries(entries: &[ZipEntry]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut central_directory = Vec::new();
+
+    for entry in entries {
+        let body = if entry.method == METHOD_DEFLATED {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder
+                .write_all(entry.data)
+                .expect("writing to a Vec cannot fail");
+            encoder.finish().expect("writing to a Vec cannot fail")
+        } else {
+            entry.data.to_vec()
+        };
+        let flags = if entry.descriptor {
+            FLAG_DATA_DESCRIPTOR
+        } else {
+            0
+        };
+
+        let mut crc = Crc::new();
+        crc.update(entry.data);
+        let mut sizes = Vec::new();
+        sizes.extend_from_slice(&crc.sum().to_le_bytes());
+        sizes.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        sizes.extend_from_slice(&(entry.data.len() as u32).to_le_bytes());
+
+        // Version, flags, method and DOS time and date (1980-01-01).
+        let mut fields = Vec::new();
+        fields.extend_from_slice(&20u16.to_le_bytes());
+        fields.extend_from_slice(&flags.to_le_bytes());
+        fields.extend_from_slice(&entry.method.to_le_bytes());
+        fields.extend_from_slice(&[0, 0, 0x21, 0]);
+        // Name and extra field lengths.
+        let mut lengths = Vec::new();
+        lengths.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+        lengths.extend_from_slice(&0u16.to_le_bytes());
+
+        central_directory.extend_from_slice(&CENTRAL_DIRECTORY_HEADER.to_le_bytes());
+        central_directory.extend_from_slice(&20u16.to_le_bytes());
+        central_directory.extend_from_slice(&fields);
+        central_directory.extend_from_slice(&sizes);
+        central_directory.extend_from_slice(&lengths);
+        // Comment length, disk, internal and external attributes, then the local header offset.
+        central_directory.extend_from_slice(&[0; 10]);
+        central_directory.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        central_directory.extend_from_slice(entry.name.as_bytes());
+
+        bytes.extend_from_slice(&LOCAL_FILE_HEADER.to_le_bytes());
+        bytes.extend_from_slice(&fields);
+        if entry.descriptor {
+            bytes.extend_from_slice(&[0; 12]);
+        } else {
+            bytes.extend_from_slice(&sizes);
+        }
+        bytes.extend_from_slice(&lengths);
+        bytes.extend_from_slice(entry.name.as_bytes());
+        bytes.extend_from_slice(&body);
+        if entry.descriptor {
+            bytes.extend_from_slice(&DATA_DESCRIPTOR.to_le_bytes());
+            bytes.extend_from_slice(&sizes);
+        }
+    }
+
+    let offset = bytes.len() as u32;
+    bytes.extend_from_slice(&central_directory);
+    bytes.extend_from_slice(&END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(&(central_directory.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&offset.to_le_bytes());
+    bytes.extend_from_slice(&0u16.to_le_bytes());
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Detector, Profile, archive};
+    use std::ops::ControlFlow;
+
+    #[test]
+    fn test_examples_detect_as_their_type() {
+        for file_type in FileType::ALL {
+            let detector = match file_type {
+                FileType::Html => Detector::new().with_profile(Profile::WebUpload),
+                _ => Detector::new(),
+            };
+            for example in generate(file_type) {
+                assert_eq!(detector.detect(&example), file_type);
+            }
+        }
+        assert!(generate(FileType::Unknown).is_empty());
+    }
+
+    #[test]
+    fn test_example_zips_walk_cleanly() {
+        for file_type in [FileType::Shapefile, FileType::Excel, FileType::Gtfs] {
+            let mut entries = 0;
+            let result = archive::walk(
+                &generate(file_type)[0][..],
+                archive::DEFAULT_PREFIX_LEN,
+                |_, _, _| {
+                    entries += 1;
+                    ControlFlow::<()>::Continue(())
+                },
+            );
+
+            assert!(result.unwrap().is_none());
+            assert!(entries >= 3);
+        }
+    }
+
+    #[test]
+    fn test_sqlite_varint() {
+        let mut out = Vec::new();
+        sqlite_varint(&mut out, 4326);
+        assert_eq!(out, [0xA1, 0x66]);
+
+        out.clear();
+        sqlite_varint(&mut out, -1i64 as u64);
+        assert_eq!(out, [0xFF; 9]);
+    }
+}
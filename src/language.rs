@@ -0,0 +1,247 @@
+use crate::FileType;
+use crate::text::{split_record, string_members, utf8_prefix};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Below this many letters there is too little text for n-gram counts to mean anything.
+const MIN_LETTERS: usize = 16;
+
+/// How many times the runner-up's score the winner needs. Lists of names that are mostly not words
+/// match a little of every language, and no hint beats a wrong one.
+const MIN_MARGIN: f32 = 1.5;
+
+/// How many CSV rows contribute text, header included.
+const CSV_SAMPLE_ROWS: usize = 50;
+
+// The most telling character n-grams of each language, mostly trigrams plus a few distinctive
+// letters, most frequent first. Spaces mark word boundaries. Every profile has the same length so
+// none wins on size alone, and n-grams common in English words are left out of the others.
+const ENGLISH: &[&str] = &[
+    " th", "the", "he ", "ing", "nd ", " an", "and", "ng ", " of", "of ", "ed ", " in", "ion",
+    "tio", "er ", "in ", " to", "to ", "ent", "re ", "es ", "at ", "on ", "is ", " co", "ati",
+    "ly ", "for", "ter", " st",
+];
+
+const WELSH: &[&str] = &[
+    " y ", "yn ", " yn", "dd ", "ydd", "au ", " cy", "ol ", " gw", " ei", "odd", "gan", "eth",
+    "yr ", " yr", "iad", "rth", "wyd", "nol", "aet", "wr ", "edd", " ff", " ll", " rh", "rhy",
+    "ych", "fyn", "ŵ", "ŷ",
+];
+
+const FRENCH: &[&str] = &[
+    " de", "de ", "es ", "ent", " le", "le ", "ion", " la", "la ", "les", " et", "et ", "tio",
+    "nt ", "on ", "re ", "que", "ue ", " pa", "des", "men", "ati", "our", " qu", "est", "ne ",
+    "ons", "é", "è", " du",
+];
+
+const GERMAN: &[&str] = &[
+    "en ", "er ", "ch ", "der", "die", "ie ", "ein", "sch", "ich", " de", " di", "nd ", "und",
+    " un", "cht", "den", "che", "gen", "ten", "te ", "ung", "ine", " ei", " zu", "ber", "auf",
+    "ver", "ß", "ü", "ä",
+];
+
+const SPANISH: &[&str] = &[
+    " de", "de ", "os ", "es ", " la", "la ", "el ", "ent", " el", "as ", "ión", "ado", " co",
+    "que", "ue ", " en", "en ", "aci", "del", "los", " lo", "nte", "ara", " pa", "con", "ien",
+    "tra", "por", "est", "ñ",
+];
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Language {
+    English,
+    Welsh,
+    French,
+    German,
+    Spanish,
+}
+
+impl Language {
+    const ALL: [Language; 5] = [
+        Language::English,
+        Language::Welsh,
+        Language::French,
+        Language::German,
+        Language::Spanish,
+    ];
+
+    /// ISO 639-1 code, e.g. `cy` for Welsh.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Welsh => "cy",
+            Language::French => "fr",
+            Language::German => "de",
+            Language::Spanish => "es",
+        }
+    }
+
+    fn profile(&self) -> &'static [&'static str] {
+        match self {
+            Language::English => ENGLISH,
+            Language::Welsh => WELSH,
+            Language::French => FRENCH,
+            Language::German => GERMAN,
+            Language::Spanish => SPANISH,
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Language::English => "English",
+            Language::Welsh => "Welsh",
+            Language::French => "French",
+            Language::German => "German",
+            Language::Spanish => "Spanish",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A best guess at the language of a file's text. `confidence` is the winning language's share of
+/// the combined score, so 1.0 means no other language matched at all.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LanguageHint {
+    pub language: Language,
+    pub confidence: f32,
+}
+
+/// Guesses the language of the header and attribute text of CSV and GeoJSON data. CSV fields are
+/// split on `delimiter`.
+pub fn sniff(file_type: FileType, bytes: &[u8], delimiter: u8) -> Option<LanguageHint> {
+    let text = utf8_prefix(bytes);
+
+    let mut sample = String::new();
+    match file_type {
+        FileType::Csv => {
+            for line in text.lines().take(CSV_SAMPLE_ROWS) {
+                for field in split_record(line, delimiter) {
+                    sample.push_str(field);
+                    sample.push(' ');
+                }
+            }
+        }
+        FileType::Geojson => {
+            for (key, value) in string_members(text) {
+                if key != "type" {
+                    sample.push_str(value);
+                    sample.push(' ');
+                }
+            }
+        }
+        _ => return None,
+    }
+
+    guess(&sample)
+}
+
+/// Guesses the language of free text from its character n-gram frequencies.
+pub fn guess(text: &str) -> Option<LanguageHint> {
+    let normalised = normalise(text);
+    if normalised.chars().filter(|c| c.is_alphabetic()).count() < MIN_LETTERS {
+        return None;
+    }
+
+    let chars: Vec<char> = normalised.chars().collect();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for n in 1..=3 {
+        for window in chars.windows(n) {
+            *counts.entry(window.iter().collect()).or_insert(0) += 1;
+        }
+    }
+
+    let mut scores: Vec<(Language, f32)> = Language::ALL
+        .iter()
+        .map(|&language| {
+            // Earlier n-grams weigh more, and the total weight evens out any difference in length.
+            let profile = language.profile();
+            let weights = (1..=profile.len())
+                .rev()
+                .map(|w| w as f32 / profile.len() as f32);
+            let (score, weight_sum) = profile.iter().zip(weights).fold(
+                (0.0, 0.0),
+                |(score, weight_sum), (gram, weight)| {
+                    let count = counts.get(*gram).copied().unwrap_or(0) as f32;
+                    (score + count * weight, weight_sum + weight)
+                },
+            );
+            (language, score / weight_sum)
+        })
+        .collect();
+
+    let total: f32 = scores.iter().map(|(_, score)| score).sum();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let (language, best) = scores[0];
+    let runner_up = scores[1].1;
+
+    if best <= 0.0 || best < runner_up * MIN_MARGIN {
+        return None;
+    }
+
+    Some(LanguageHint {
+        language,
+        confidence: best / total,
+    })
+}
+
+// Lowercase letters only, one space between words and padding at each end so word boundaries
+// show up in trigrams.
+fn normalise(text: &str) -> String {
+    let mut normalised = String::from(" ");
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_alphabetic() || c == '\'' {
+            normalised.push(c);
+        } else if !normalised.ends_with(' ') {
+            normalised.push(' ');
+        }
+    }
+    if !normalised.ends_with(' ') {
+        normalised.push(' ');
+    }
+    normalised
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guess_welsh_and_english() {
+        let welsh = "Enw'r ardal,Disgrifiad\nCaerdydd,Mae'r ardal yn cynnwys y parc a'r llyfrgell\nAbertawe,Ardal gadwraeth ger y môr\n";
+        let english = "Area name,Description\nCardiff,The area includes the park and the library\nSwansea,Conservation area by the sea\n";
+
+        let hint = sniff(FileType::Csv, welsh.as_bytes(), b',').unwrap();
+        assert_eq!(hint.language, Language::Welsh);
+        assert_eq!(hint.language.code(), "cy");
+
+        let hint = sniff(FileType::Csv, english.as_bytes(), b',').unwrap();
+        assert_eq!(hint.language, Language::English);
+    }
+
+    #[test]
+    fn test_guess_geojson_properties() {
+        let geojson = r#"{"type":"FeatureCollection","features":[{"type":"Feature","properties":{"enw":"Gorsaf reilffordd y dref","disgrifiad":"Mae'r orsaf ar agor bob dydd"}}]}"#;
+
+        let hint = sniff(FileType::Geojson, geojson.as_bytes(), b',').unwrap();
+        assert_eq!(hint.language, Language::Welsh);
+    }
+
+    #[test]
+    fn test_guess_needs_enough_text() {
+        assert_eq!(guess("id,x,y"), None);
+        assert_eq!(sniff(FileType::Parquet, b"the and of the", b','), None);
+    }
+
+    #[test]
+    fn test_english_place_names_are_not_welsh() {
+        let addresses =
+            "Address,Town\n12 Hill Street,Wellington\n3 Mill Lane,Ashford\n45 Wall Road,Cardiff\n";
+        let roads = "road_name,status\nHigh Street,Open\nMill Road,Closed for works\nStation Road,Open\nChurch Lane,Closed\n";
+
+        for csv in [addresses, roads] {
+            let hint = sniff(FileType::Csv, csv.as_bytes(), b',');
+            assert_ne!(hint.map(|hint| hint.language), Some(Language::Welsh));
+        }
+    }
+}
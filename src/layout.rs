@@ -0,0 +1,497 @@
+use crate::archive::{self, ArchiveError, DEFAULT_PREFIX_LEN};
+use crate::{DetectionStatus, Detector, FileFinch, FileType};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, ReadDir};
+use std::io::{self, Read};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+
+/// Picks out the files a [`Rule`] is about.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Matcher {
+    FileType(FileType),
+    /// Case-insensitive file extension without the dot, for things detection has no type for
+    /// (e.g. `xml` metadata).
+    Extension(String),
+}
+
+impl Matcher {
+    pub fn extension(extension: &str) -> Self {
+        Matcher::Extension(extension.trim_start_matches('.').to_lowercase())
+    }
+
+    fn matches(&self, path: &str, file_type: FileType) -> bool {
+        match self {
+            Matcher::FileType(expected) => *expected == file_type,
+            Matcher::Extension(extension) => Path::new(path)
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case(extension)),
+        }
+    }
+}
+
+impl fmt::Display for Matcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Matcher::FileType(file_type) => write!(f, "{}", file_type),
+            Matcher::Extension(extension) => write!(f, "*.{}", extension),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Count {
+    Exactly(usize),
+    AtLeast(usize),
+    AtMost(usize),
+    Between(usize, usize),
+}
+
+impl Count {
+    pub const ONE: Count = Count::Exactly(1);
+    pub const OPTIONAL: Count = Count::AtMost(1);
+    pub const ANY: Count = Count::AtLeast(0);
+
+    fn allows(&self, n: usize) -> bool {
+        match *self {
+            Count::Exactly(expected) => n == expected,
+            Count::AtLeast(min) => n >= min,
+            Count::AtMost(max) => n <= max,
+            Count::Between(min, max) => (min..=max).contains(&n),
+        }
+    }
+}
+
+impl fmt::Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Count::Exactly(n) => write!(f, "exactly {}", n),
+            Count::AtLeast(n) => write!(f, "at least {}", n),
+            Count::AtMost(n) => write!(f, "at most {}", n),
+            Count::Between(min, max) => write!(f, "between {} and {}", min, max),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Rule {
+    pub matcher: Matcher,
+    pub count: Count,
+}
+
+/// A file found in a delivery, with its path relative to the delivery root and the status saying
+/// why it is `Unknown` when it is (e.g. an empty placeholder).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Entry {
+    pub path: String,
+    pub file_type: FileType,
+    pub status: DetectionStatus,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Finding {
+    Satisfied { rule: Rule, paths: Vec<String> },
+    WrongCount { rule: Rule, paths: Vec<String> },
+    Unexpected(Entry),
+}
+
+impl Finding {
+    pub fn passed(&self) -> bool {
+        matches!(self, Finding::Satisfied { .. })
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Finding::Satisfied { rule, paths } => write!(
+                f,
+                "PASS {}: expected {}, found {}",
+                rule.matcher,
+                rule.count,
+                paths.len()
+            ),
+            Finding::WrongCount { rule, paths } => write!(
+                f,
+                "FAIL {}: expected {}, found {} {:?}",
+                rule.matcher,
+                rule.count,
+                paths.len(),
+                paths
+            ),
+            Finding::Unexpected(entry) if entry.status == DetectionStatus::Detected => {
+                write!(
+                    f,
+                    "FAIL unexpected file {} ({})",
+                    entry.path, entry.file_type
+                )
+            }
+            Finding::Unexpected(entry) => write!(
+                f,
+                "FAIL unexpected file {} ({}, {})",
+                entry.path, entry.file_type, entry.status
+            ),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Report {
+    pub findings: Vec<Finding>,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.findings.iter().all(Finding::passed)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &Finding> {
+        self.findings.iter().filter(|finding| !finding.passed())
+    }
+}
+
+/// The expected structure of a delivery, e.g. exactly one GeoPackage, any number of CSVs and one
+/// metadata XML.
+///
+/// Each file counts towards the first rule that matches it, so put narrower rules first. Files that
+/// match no rule fail validation unless [`Layout::with_unexpected_files`] allows them.
+#[derive(Debug, Default, Clone)]
+pub struct Layout {
+    rules: Vec<Rule>,
+    allow_unexpected: bool,
+    detector: Option<Detector>,
+}
+
+impl Layout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_rule(mut self, matcher: Matcher, count: Count) -> Self {
+        self.rules.push(Rule { matcher, count });
+        self
+    }
+
+    pub fn with_unexpected_files(mut self, allow: bool) -> Self {
+        self.allow_unexpected = allow;
+        self
+    }
+
+    /// Detects files with `detector` in [`Layout::validate_dir`] and [`Layout::validate_archive`]
+    /// rather than the shared default one.
+    pub fn with_detector(mut self, detector: Detector) -> Self {
+        self.detector = Some(detector);
+        self
+    }
+
+    fn detector(&self) -> &Detector {
+        self.detector
+            .as_ref()
+            .unwrap_or_else(|| FileFinch::detector())
+    }
+
+    pub fn validate(&self, entries: &[Entry]) -> Report {
+        let mut matched: Vec<Vec<String>> = vec![Vec::new(); self.rules.len()];
+        let mut unexpected = Vec::new();
+
+        for entry in entries {
+            match self
+                .rules
+                .iter()
+                .position(|rule| rule.matcher.matches(&entry.path, entry.file_type))
+            {
+                Some(index) => matched[index].push(entry.path.clone()),
+                None if self.allow_unexpected => {}
+                None => unexpected.push(Finding::Unexpected(entry.clone())),
+            }
+        }
+
+        let mut findings: Vec<Finding> = self
+            .rules
+            .iter()
+            .zip(matched)
+            .map(|(rule, paths)| {
+                let rule = rule.clone();
+                if rule.count.allows(paths.len()) {
+                    Finding::Satisfied { rule, paths }
+                } else {
+                    Finding::WrongCount { rule, paths }
+                }
+            })
+            .collect();
+        findings.extend(unexpected);
+
+        Report { findings }
+    }
+
+    /// Scans every file under `root` and validates them.
+    pub fn validate_dir(&self, root: &Path) -> io::Result<Report> {
+        Ok(self.validate(&scan_dir_with(self.detector(), root)?))
+    }
+
+    /// Streams through a ZIP archive and validates its entries.
+    pub fn validate_archive<R: Read>(&self, reader: R) -> Result<Report, ArchiveError> {
+        Ok(self.validate(&scan_archive_with(self.detector(), reader)?))
+    }
+}
+
+/// Detects every file under `root` with [`FileFinch::detect_file`]. Paths are relative to `root`
+/// and `/` separated, and come back sorted.
+pub fn scan_dir(root: &Path) -> io::Result<Vec<Entry>> {
+    scan_dir_with(FileFinch::detector(), root)
+}
+
+/// [`scan_dir`] with the types detected by `detector`.
+pub fn scan_dir_with(detector: &Detector, root: &Path) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+
+    for path in walk_dir(root) {
+        let path = path?;
+        let result = detector
+            .analyze_file(&path)
+            .map_err(|e| with_path(&path, e))?;
+
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        let relative = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        entries.push(Entry {
+            path: relative,
+            file_type: result.file_type,
+            status: result.status,
+        });
+    }
+
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+/// Every file under `root`, in no particular order.
+///
+/// A directory or entry that cannot be read comes back as an error naming its path, and the walk
+/// carries on with the rest. Symbolic links to directories are followed, but a directory reached a
+/// second time (through a link loop, or a link to somewhere already walked) is reported as an
+/// error instead of being walked again.
+pub fn walk_dir(root: &Path) -> WalkDir {
+    WalkDir {
+        pending: vec![root.to_path_buf()],
+        current: None,
+        visited: HashSet::new(),
+    }
+}
+
+/// Iterator returned by [`walk_dir`].
+#[derive(Debug)]
+pub struct WalkDir {
+    pending: Vec<PathBuf>,
+    current: Option<(PathBuf, ReadDir)>,
+    visited: HashSet<PathBuf>,
+}
+
+impl WalkDir {
+    fn open(&mut self, dir: PathBuf) -> io::Result<()> {
+        let canonical = fs::canonicalize(&dir).map_err(|e| with_path(&dir, e))?;
+        if !self.visited.insert(canonical) {
+            let message = format!("{}: directory already walked", dir.display());
+            return Err(io::Error::other(message));
+        }
+
+        let entries = fs::read_dir(&dir).map_err(|e| with_path(&dir, e))?;
+        self.current = Some((dir, entries));
+        Ok(())
+    }
+}
+
+impl Iterator for WalkDir {
+    type Item = io::Result<PathBuf>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Some((dir, entries)) = &mut self.current else {
+                let dir = self.pending.pop()?;
+                if let Err(e) = self.open(dir) {
+                    return Some(Err(e));
+                }
+                continue;
+            };
+
+            let entry = match entries.next() {
+                Some(Ok(entry)) => entry,
+                Some(Err(e)) => return Some(Err(with_path(dir, e))),
+                None => {
+                    self.current = None;
+                    continue;
+                }
+            };
+
+            // The entry's own file type does not follow links, so ask the target for those.
+            let path = entry.path();
+            let is_dir = match entry.file_type() {
+                Ok(file_type) if file_type.is_symlink() => fs::metadata(&path).map(|m| m.is_dir()),
+                Ok(file_type) => Ok(file_type.is_dir()),
+                Err(e) => Err(e),
+            };
+            match is_dir {
+                Ok(true) => self.pending.push(path),
+                Ok(false) => return Some(Ok(path)),
+                Err(e) => return Some(Err(with_path(&path, e))),
+            }
+        }
+    }
+}
+
+fn with_path(path: &Path, e: io::Error) -> io::Error {
+    io::Error::new(e.kind(), format!("{}: {}", path.display(), e))
+}
+
+/// Detects every entry of a ZIP archive, in archive order.
+pub fn scan_archive<R: Read>(reader: R) -> Result<Vec<Entry>, ArchiveError> {
+    scan_archive_with(FileFinch::detector(), reader)
+}
+
+/// [`scan_archive`] with the types detected by `detector`.
+pub fn scan_archive_with<R: Read>(
+    detector: &Detector,
+    reader: R,
+) -> Result<Vec<Entry>, ArchiveError> {
+    let mut entries = Vec::new();
+    archive::walk_with(detector, reader, DEFAULT_PREFIX_LEN, |name, _, result| {
+        entries.push(Entry {
+            path: name.to_string(),
+            file_type: result.file_type,
+            status: result.status,
+        });
+        ControlFlow::<()>::Continue(())
+    })?;
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, file_type: FileType) -> Entry {
+        let status = match file_type {
+            FileType::Unknown => DetectionStatus::Unrecognised,
+            _ => DetectionStatus::Detected,
+        };
+        Entry {
+            path: path.to_string(),
+            file_type,
+            status,
+        }
+    }
+
+    fn supplier_layout() -> Layout {
+        Layout::new()
+            .with_rule(Matcher::FileType(FileType::Geopackage), Count::ONE)
+            .with_rule(Matcher::FileType(FileType::Csv), Count::ANY)
+            .with_rule(Matcher::extension("xml"), Count::ONE)
+    }
+
+    #[test]
+    fn test_validate_passing_delivery() {
+        let report = supplier_layout().validate(&[
+            entry("roads.gpkg", FileType::Geopackage),
+            entry("lookup/a.csv", FileType::Csv),
+            entry("lookup/b.csv", FileType::Csv),
+            entry("metadata.XML", FileType::Unknown),
+        ]);
+
+        assert!(report.passed());
+        assert_eq!(report.findings.len(), 3);
+    }
+
+    #[test]
+    fn test_validate_failing_delivery() {
+        let report = supplier_layout().validate(&[
+            entry("roads.gpkg", FileType::Geopackage),
+            entry("roads_v2.gpkg", FileType::Geopackage),
+            entry("readme.png", FileType::Png),
+            Entry {
+                status: DetectionStatus::Empty,
+                ..entry("lookup/pending.csv", FileType::Unknown)
+            },
+        ]);
+
+        let failures: Vec<String> = report.failures().map(|f| f.to_string()).collect();
+        assert_eq!(
+            failures,
+            vec![
+                r#"FAIL Geopackage: expected exactly 1, found 2 ["roads.gpkg", "roads_v2.gpkg"]"#,
+                "FAIL *.xml: expected exactly 1, found 0 []",
+                "FAIL unexpected file readme.png (PNG)",
+                "FAIL unexpected file lookup/pending.csv (Unknown, Empty)",
+            ]
+        );
+
+        let lenient = supplier_layout().with_unexpected_files(true).validate(&[
+            entry("roads.gpkg", FileType::Geopackage),
+            entry("metadata.xml", FileType::Unknown),
+            entry("readme.png", FileType::Png),
+        ]);
+        assert!(lenient.passed());
+    }
+
+    #[test]
+    fn test_validate_dir() {
+        let root = std::env::temp_dir().join(format!("file_finch_layout_{}", std::process::id()));
+        std::fs::create_dir_all(root.join("lookup")).unwrap();
+        std::fs::write(root.join("roads.gpkg"), b"SQLite format 3\x00").unwrap();
+        std::fs::write(root.join("lookup/codes.csv"), b"code,label\n1,a\n").unwrap();
+        std::fs::write(root.join("metadata.xml"), b"<gmd:MD_Metadata/>").unwrap();
+
+        let report = supplier_layout().validate_dir(&root).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert!(report.passed(), "{:?}", report);
+    }
+
+    #[test]
+    fn test_validate_archive_with_detector() {
+        let zip = crate::examples::zip(&[("sites.tsv", b"id\tname\n1\talpha\n2\tbeta\n")]);
+        let layout = Layout::new().with_rule(Matcher::FileType(FileType::Csv), Count::ONE);
+
+        assert!(!layout.validate_archive(&zip[..]).unwrap().passed());
+        let layout = layout.with_detector(Detector::new().with_profile(crate::Profile::DataLake));
+        assert!(layout.validate_archive(&zip[..]).unwrap().passed());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_dir_reports_bad_links_and_goes_on() {
+        use std::os::unix::fs::symlink;
+
+        let root = std::env::temp_dir().join(format!("file_finch_walk_{}", std::process::id()));
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("a.csv"), b"id\n1\n").unwrap();
+        fs::write(root.join("sub/b.csv"), b"id\n2\n").unwrap();
+        symlink(&root, root.join("sub/loop")).unwrap();
+        symlink(root.join("missing.csv"), root.join("dangling.csv")).unwrap();
+
+        let (mut files, mut errors) = (Vec::new(), Vec::new());
+        for path in walk_dir(&root) {
+            match path {
+                Ok(path) => files.push(path),
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+        fs::remove_dir_all(&root).unwrap();
+
+        files.sort();
+        errors.sort();
+        assert_eq!(files, vec![root.join("a.csv"), root.join("sub/b.csv")]);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with(&root.join("dangling.csv").display().to_string()));
+        assert!(
+            errors[1].ends_with("directory already walked"),
+            "{}",
+            errors[1]
+        );
+    }
+}
@@ -1,4 +1,24 @@
+pub mod archive;
+mod arrow;
+pub mod cache;
+mod detector;
+mod examples;
+pub mod language;
+pub mod layout;
+mod parquet;
+pub mod route;
+pub mod temporal;
+mod text;
+
+pub use arrow::ArrowCompression;
+pub use detector::{Detector, MIN_DETECTABLE_LEN, Profile};
+use language::LanguageHint;
 use std::fmt;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use temporal::TemporalExtent;
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum FileType {
@@ -10,9 +30,36 @@ pub enum FileType {
     Parquet,
     Arrow,
     Png,
+    /// A GTFS transit feed, a ZIP of CSV tables.
+    Gtfs,
+    /// An HTML page, usually an error page saved in place of the file that was asked for. Only
+    /// reported under [`Profile::WebUpload`].
+    Html,
     Unknown,
 }
 
+impl FileType {
+    pub const ALL: [FileType; 11] = [
+        FileType::Geopackage,
+        FileType::Shapefile,
+        FileType::Geojson,
+        FileType::Excel,
+        FileType::Csv,
+        FileType::Parquet,
+        FileType::Arrow,
+        FileType::Png,
+        FileType::Gtfs,
+        FileType::Html,
+        FileType::Unknown,
+    ];
+
+    /// Minimal valid samples of this format, generated rather than stored, for tests and demos.
+    /// Each one detects as `self`, under [`Profile::WebUpload`] for `Html`. `Unknown` has none.
+    pub fn examples(&self) -> Vec<Vec<u8>> {
+        examples::generate(*self)
+    }
+}
+
 // TODO: I should add in Arvo and other common files types used in data processing/engineering
 impl fmt::Display for FileType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -25,177 +72,207 @@ impl fmt::Display for FileType {
             FileType::Parquet => "Parquet",
             FileType::Arrow => "Arrow",
             FileType::Png => "PNG",
+            FileType::Gtfs => "GTFS",
+            FileType::Html => "HTML",
             FileType::Unknown => "Unknown",
         };
         write!(f, "{}", name)
     }
 }
 
-pub struct FileFinch;
+/// Parses the names produced by `Display`, ignoring case.
+impl FromStr for FileType {
+    type Err = String;
 
-impl FileFinch {
-    pub fn detect(bytes: &[u8]) -> FileType {
-        if let Some(file_type) = Self::detect_by_magic(bytes) {
-            return file_type;
-        }
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FileType::ALL
+            .into_iter()
+            .find(|file_type| file_type.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown file type: {}", s))
+    }
+}
 
-        if let Ok(file_type) = Self::detect_geojson(bytes) {
-            return file_type;
-        }
+/// Why detection came back with the type it did.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum DetectionStatus {
+    /// A format was recognised.
+    Detected,
+    /// No bytes at all, or nothing but whitespace.
+    Empty,
+    /// Fewer than [`MIN_DETECTABLE_LEN`] bytes, too few to tell formats apart.
+    TooSmall,
+    /// Enough content, but no known format matched.
+    Unrecognised,
+}
 
-        if Self::looks_like_csv(bytes) {
-            return FileType::Csv;
-        }
+impl fmt::Display for DetectionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DetectionStatus::Detected => "Detected",
+            DetectionStatus::Empty => "Empty",
+            DetectionStatus::TooSmall => "Too small",
+            DetectionStatus::Unrecognised => "Unrecognised",
+        };
+        write!(f, "{}", name)
+    }
+}
 
-        FileType::Unknown
+/// Parses the names produced by `Display`, ignoring case.
+impl FromStr for DetectionStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            DetectionStatus::Detected,
+            DetectionStatus::Empty,
+            DetectionStatus::TooSmall,
+            DetectionStatus::Unrecognised,
+        ]
+        .into_iter()
+        .find(|status| status.to_string().eq_ignore_ascii_case(s))
+        .ok_or_else(|| format!("Unknown detection status: {}", s))
     }
+}
 
-    pub fn detect_from_path(path: &str, bytes: &[u8]) -> FileType {
-        let detected = Self::detect(bytes);
+/// Something found during detection that matters to whoever reads the file next.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Detail {
+    /// Arrow record batch bodies are compressed, so the reader needs that codec built in.
+    ArrowBodyCompression(ArrowCompression),
+}
 
-        if detected != FileType::Unknown {
-            return detected;
+impl fmt::Display for Detail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Detail::ArrowBodyCompression(codec) => write!(f, "{} compressed bodies", codec),
         }
+    }
+}
 
-        if let Some(extension) = std::path::Path::new(path)
-            .extension()
-            .and_then(|e| e.to_str())
-        {
-            match extension.to_lowercase().as_str() {
-                "csv" => return FileType::Csv,
-                "json" | "geojson" => {
-                    if Self::detect_geojson(bytes).is_ok() {
-                        return FileType::Geojson;
-                    }
-                }
-                _ => {}
-            }
-        }
+/// Parses the descriptions produced by `Display`, ignoring case.
+impl FromStr for Detail {
+    type Err = String;
 
-        FileType::Unknown
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [ArrowCompression::Lz4Frame, ArrowCompression::Zstd]
+            .map(Detail::ArrowBodyCompression)
+            .into_iter()
+            .find(|detail| detail.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown detail: {}", s))
     }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct DetectionResult {
+    pub file_type: FileType,
+    pub status: DetectionStatus,
+    pub detail: Option<Detail>,
+}
 
-    fn detect_by_magic(bytes: &[u8]) -> Option<FileType> {
-        match bytes {
-            [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some(FileType::Png),
-            [0x50, 0x4B, 0x03, 0x04, rest @ ..] => Self::detect_zip_content(rest),
-            [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1, ..] => Some(FileType::Excel),
-            [0x50, 0x41, 0x52, 0x31, ..] => Some(FileType::Parquet),
-            bytes if bytes.starts_with(b"SQLite format 3\x00") => Some(FileType::Geopackage),
-            bytes if bytes.starts_with(b"ARROW1") => Some(FileType::Arrow),
-            bytes if Self::is_arrow_ipc_stream(bytes) => Some(FileType::Arrow),
-            _ => None,
+impl DetectionResult {
+    fn from_file_type(file_type: FileType) -> Self {
+        let status = match file_type {
+            FileType::Unknown => DetectionStatus::Unrecognised,
+            _ => DetectionStatus::Detected,
+        };
+        DetectionResult {
+            file_type,
+            status,
+            detail: None,
         }
     }
 
-    fn detect_zip_content(bytes: &[u8]) -> Option<FileType> {
-        let excel_patterns: &[&[u8]] = &[
-            b"xl/worksheets",
-            b"xl/_rels",
-            b"docProps/",
-            b"[Content_Types]",
-            b"xl/workbook",
-            b"xl/styles",
-            b"xl/theme",
-            b"xl/strings",
-            b"xl/charts",
-            b"xl/drawings",
-            b"xl/sharedStrings",
-            b"xl/metadata",
-            b"xl/calc",
-        ];
-
-        let shapefile_patterns: &[&[u8]] = &[b".shp", b".dbf", b".prj", b".shx"];
-
-        let is_excel = excel_patterns
-            .iter()
-            .any(|&pattern| bytes.windows(pattern.len()).any(|window| window == pattern));
-
-        let is_shapefile = shapefile_patterns
-            .iter()
-            .any(|&pattern| bytes.windows(pattern.len()).any(|window| window == pattern));
+    fn with_detail(mut self, detail: Option<Detail>) -> Self {
+        self.detail = detail;
+        self
+    }
 
-        match (is_excel, is_shapefile) {
-            (true, false) => Some(FileType::Excel),
-            (false, true) => Some(FileType::Shapefile),
-            _ => None,
+    fn unknown(status: DetectionStatus) -> Self {
+        DetectionResult {
+            file_type: FileType::Unknown,
+            status,
+            detail: None,
         }
     }
+}
 
-    fn detect_geojson(bytes: &[u8]) -> Result<FileType, ()> {
-        if let Ok(text) = std::str::from_utf8(bytes) {
-            let text_lower = text.trim_start().to_lowercase();
-
-            if text_lower.starts_with("{")
-                && text_lower.contains(r#""type""#)
-                && (text_lower.contains(r#""featurecollection""#)
-                    || text_lower.contains(r#""feature""#)
-                    || text_lower.contains(r#""geometry""#))
-            {
-                return Ok(FileType::Geojson);
-            }
+/// The type followed by the detail, or by the status when the type is `Unknown`, e.g.
+/// `Unknown (Too small)`.
+impl fmt::Display for DetectionResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file_type)?;
+        match (self.status, self.detail) {
+            (DetectionStatus::Detected, Some(detail)) => write!(f, " ({})", detail),
+            (DetectionStatus::Detected, None) => Ok(()),
+            (status, _) => write!(f, " ({})", status),
         }
-        Err(())
     }
+}
 
-    fn looks_like_csv(bytes: &[u8]) -> bool {
-        if bytes.is_empty() {
-            return false;
-        }
+/// Drops the status and detail, for callers still passing plain `FileType`s around.
+impl From<DetectionResult> for FileType {
+    fn from(result: DetectionResult) -> Self {
+        result.file_type
+    }
+}
 
-        if let Ok(text) = std::str::from_utf8(bytes) {
-            let sample = if text.len() > 1000 {
-                &text[..1000]
-            } else {
-                text
-            };
+/// Optional extras computed by [`FileFinch::inspect`] on top of plain detection.
+///
+/// CSV is sampled on the delimiter that detection matched, so under [`Profile::DataLake`] a tab
+/// separated file has its tab separated columns read.
+#[derive(Debug, Clone, Default)]
+pub struct InspectOptions {
+    /// Sample date/datetime columns to estimate the dataset's temporal extent.
+    pub temporal_extent: bool,
+    /// Guess the language of header and attribute text.
+    pub language: bool,
+}
 
-            let lines: Vec<&str> = sample.lines().take(5).collect();
-            if lines.is_empty() {
-                return false;
-            }
+#[derive(Debug, PartialEq, Clone)]
+pub struct Inspection {
+    pub file_type: FileType,
+    pub status: DetectionStatus,
+    pub detail: Option<Detail>,
+    pub temporal_extent: Option<TemporalExtent>,
+    pub language: Option<LanguageHint>,
+}
 
-            let delimiter_counts: Vec<usize> =
-                lines.iter().map(|line| line.matches(',').count()).collect();
+/// Static shortcuts over a shared default [`Detector`].
+///
+/// `detect` and `detect_from_path` predate [`Detector`] and are kept for existing callers. A few
+/// edge cases (tiny or blank input, GTFS feeds, continuation-framed Arrow streams, text with a
+/// multi-byte character across the 1000 byte CSV sample limit) now give different answers, listed
+/// in the README. New code should hold a [`Detector`] and call [`Detector::analyze`] or
+/// [`Detector::analyze_from_path`], which also say why a result is `Unknown`.
+pub struct FileFinch;
 
-            if delimiter_counts.is_empty() {
-                return false;
-            }
+impl FileFinch {
+    pub(crate) fn detector() -> &'static Detector {
+        static DETECTOR: OnceLock<Detector> = OnceLock::new();
+        DETECTOR.get_or_init(Detector::new)
+    }
 
-            let first_count = delimiter_counts[0];
-            first_count > 0 && delimiter_counts.iter().all(|&count| count == first_count)
-        } else {
-            false
-        }
+    /// Same as `FileType::from(Detector::new().analyze(bytes))`.
+    pub fn detect(bytes: &[u8]) -> FileType {
+        Self::detector().detect(bytes)
     }
 
-    fn is_arrow_ipc_stream(bytes: &[u8]) -> bool {
-        if bytes.len() < 8 {
-            return false;
-        }
+    /// Same as `FileType::from(Detector::new().analyze_from_path(path, bytes))`.
+    pub fn detect_from_path(path: &str, bytes: &[u8]) -> FileType {
+        Self::detector().detect_from_path(path, bytes)
+    }
 
-        let continuation = [0xFF, 0xFF, 0xFF, 0xFF];
-        if bytes[4..8] == continuation && bytes[0..4] == [0x00, 0x00, 0x00, 0x00] {
-            return true;
-        }
+    /// Detects a file on disk from its first [`archive::DEFAULT_PREFIX_LEN`] bytes and its name.
+    pub fn detect_file(path: &Path) -> io::Result<FileType> {
+        Self::detector().detect_file(path)
+    }
 
-        if bytes.len() >= 8 {
-            let message_length = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
-            let metadata_length = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
-
-            if (8..0x100000).contains(&message_length)
-                && metadata_length > 0
-                && metadata_length < message_length
-                && (message_length as usize) <= bytes.len()
-                && bytes.len() > 8
-                && !bytes[8..].starts_with(b"{")
-                && !bytes[8..].starts_with(b"\"")
-            {
-                return true;
-            }
-        }
+    pub fn analyze(bytes: &[u8]) -> DetectionResult {
+        Self::detector().analyze(bytes)
+    }
 
-        false
+    pub fn inspect(bytes: &[u8], options: &InspectOptions) -> Inspection {
+        Self::detector().inspect(bytes, options)
     }
 }
 
@@ -277,6 +354,153 @@ mod tests {
         assert_eq!(FileFinch::detect(&png_header), FileType::Png);
     }
 
+    #[test]
+    fn test_inspect_temporal_extent() {
+        let csv_data = b"site,date\nA,2022-04-01\nB,2023-09-30\n";
+
+        let inspection = FileFinch::inspect(csv_data, &InspectOptions::default());
+        assert_eq!(inspection.file_type, FileType::Csv);
+        assert_eq!(inspection.temporal_extent, None);
+
+        let options = InspectOptions {
+            temporal_extent: true,
+            ..Default::default()
+        };
+        let extent = FileFinch::inspect(csv_data, &options)
+            .temporal_extent
+            .unwrap();
+        assert_eq!(extent.start.to_string(), "2022-04-01T00:00:00");
+        assert_eq!(extent.end.to_string(), "2023-09-30T00:00:00");
+    }
+
+    #[test]
+    fn test_inspect_language() {
+        let csv_data = "enw,disgrifiad\nCaerdydd,Prifddinas Cymru a'r ddinas fwyaf yn y wlad\n";
+        let options = InspectOptions {
+            language: true,
+            ..Default::default()
+        };
+
+        let hint = FileFinch::inspect(csv_data.as_bytes(), &options)
+            .language
+            .unwrap();
+        assert_eq!(hint.language, language::Language::Welsh);
+    }
+
+    #[test]
+    fn test_empty_inputs_across_apis() {
+        assert_eq!(FileFinch::detect(b""), FileType::Unknown);
+        assert_eq!(FileFinch::analyze(b"").status, DetectionStatus::Empty);
+        assert_eq!(FileFinch::analyze(b"\n\n").status, DetectionStatus::Empty);
+        assert_eq!(FileFinch::analyze(b"{}").status, DetectionStatus::TooSmall);
+
+        let inspection = FileFinch::inspect(b"   ", &InspectOptions::default());
+        assert_eq!(inspection.file_type, FileType::Unknown);
+        assert_eq!(inspection.status, DetectionStatus::Empty);
+    }
+
+    #[test]
+    fn test_file_type_from_str() {
+        for file_type in FileType::ALL {
+            assert_eq!(file_type.to_string().parse::<FileType>(), Ok(file_type));
+        }
+        assert_eq!("geojson".parse::<FileType>(), Ok(FileType::Geojson));
+        assert!("Avro".parse::<FileType>().is_err());
+    }
+
+    #[test]
+    fn test_detection_result_display_and_parts_from_str() {
+        let results = [
+            (FileFinch::analyze(b"id,name\n1,a\n"), "CSV"),
+            (FileFinch::analyze(b" \n"), "Unknown (Empty)"),
+            (FileFinch::analyze(b"a,b"), "Unknown (Too small)"),
+            (
+                FileFinch::analyze(&FileType::Arrow.examples()[1]),
+                "Arrow (ZSTD compressed bodies)",
+            ),
+        ];
+        for (result, expected) in results {
+            assert_eq!(result.to_string(), expected);
+            assert_eq!(
+                result.status.to_string().parse::<DetectionStatus>(),
+                Ok(result.status)
+            );
+            if let Some(detail) = result.detail {
+                assert_eq!(detail.to_string().parse::<Detail>(), Ok(detail));
+            }
+        }
+        assert!("Corrupt".parse::<DetectionStatus>().is_err());
+    }
+
+    #[test]
+    fn test_static_shims_keep_baseline_results() {
+        let legacy_stream = b"\x10\x00\x00\x00\x08\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
+        let doctype = b"<!DOCTYPE html>\n<html><body></body></html>";
+        let long_line = format!("a,b\na,b\n{}", "x".repeat(1200));
+
+        // (path, bytes, detect, detect_from_path) as returned before `Detector` existed.
+        let unchanged: &[(&str, &[u8], FileType, FileType)] = &[
+            ("data.csv", b"id,name\n1,a\n", FileType::Csv, FileType::Csv),
+            (
+                "data.csv",
+                b"just some text",
+                FileType::Unknown,
+                FileType::Csv,
+            ),
+            (
+                "data.txt",
+                b"just some text",
+                FileType::Unknown,
+                FileType::Unknown,
+            ),
+            (
+                "data.json",
+                br#"{"type":"Feature"}"#,
+                FileType::Geojson,
+                FileType::Geojson,
+            ),
+            ("page.html", doctype, FileType::Unknown, FileType::Unknown),
+            (
+                "data.txt",
+                long_line.as_bytes(),
+                FileType::Unknown,
+                FileType::Unknown,
+            ),
+            ("data", b"PAR1", FileType::Parquet, FileType::Parquet),
+            ("data", b"ARROW1\x00\x00", FileType::Arrow, FileType::Arrow),
+            ("data", legacy_stream, FileType::Arrow, FileType::Arrow),
+            ("", b"", FileType::Unknown, FileType::Unknown),
+        ];
+        for &(path, bytes, detected, detected_from_path) in unchanged {
+            assert_eq!(FileFinch::detect(bytes), detected);
+            assert_eq!(FileFinch::detect_from_path(path, bytes), detected_from_path);
+        }
+
+        // The changes listed in the README.
+        assert_eq!(
+            FileFinch::detect_from_path("data.csv", b""),
+            FileType::Unknown
+        );
+        assert_eq!(
+            FileFinch::detect_from_path("data.csv", b"  \n"),
+            FileType::Unknown
+        );
+        assert_eq!(FileFinch::detect(b"a,b"), FileType::Unknown);
+        assert_eq!(
+            FileFinch::detect(&FileType::Gtfs.examples()[0]),
+            FileType::Gtfs
+        );
+        assert_eq!(
+            FileFinch::detect(&FileType::Arrow.examples()[0]),
+            FileType::Arrow
+        );
+
+        // Byte 1000 falls inside the `é`, which used to panic when the CSV sample was sliced.
+        let straddling = format!("{}ab,é\n", "a,b\n".repeat(249));
+        assert!(!straddling.is_char_boundary(1000));
+        assert_eq!(FileFinch::detect(straddling.as_bytes()), FileType::Csv);
+    }
+
     #[test]
     fn test_detect_unknown() {
         let random_bytes = vec![0x12, 0x34, 0x56, 0x78];
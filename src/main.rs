@@ -1,47 +1,350 @@
-use file_finch::FileFinch;
-use muy_zipido::{
-    MuyZipido,
-    progress_bar::{Colour, Style},
-};
+use file_finch::archive::{self, DEFAULT_PREFIX_LEN};
+use file_finch::cache::DetectionCache;
+use file_finch::layout;
+use file_finch::route::{self, RouteTemplate};
+use file_finch::{Detector, FileFinch, FileType};
+use muy_zipido::progress_bar::{Colour, ProgressBar, Style};
+use std::collections::HashMap;
+use std::io::Read;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const DEFAULT_URL: &str = "https://data.london.gov.uk/download/9ca66bba-b18c-4d2b-8025-a5fe7d0d66e0/6defa131-f57e-4f86-921d-8d023c98155d/LAEI2019-nox-pm-cold-start-grid-emissions.zip";
+
+const USAGE: &str = "Usage:
+  file_finch scan <dir> [--no-cache] [--cache <file>]
+  file_finch manifest <dir> [--no-cache] [--cache <file>]
+  file_finch watch <dir> [--interval <secs>] [--move-to <template>] [--unknown-dir <dir>] [--once]
+  file_finch fetch [url]
+
+Templates for --move-to may use {type}, {filename}, {stem} and {ext},
+e.g. incoming/{type}/{filename}";
+
+struct Args {
+    command: String,
+    target: Option<String>,
+    no_cache: bool,
+    cache_path: Option<PathBuf>,
+    interval: Duration,
+    move_to: Option<RouteTemplate>,
+    unknown_dir: Option<PathBuf>,
+    once: bool,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let command = args.next().ok_or(USAGE)?;
+        let mut parsed = Args {
+            command,
+            target: None,
+            no_cache: false,
+            cache_path: None,
+            interval: Duration::from_secs(2),
+            move_to: None,
+            unknown_dir: None,
+            once: false,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--no-cache" => parsed.no_cache = true,
+                "--cache" => parsed.cache_path = Some(args.next().ok_or(USAGE)?.into()),
+                "--interval" => {
+                    let secs = args.next().ok_or(USAGE)?;
+                    let secs: u64 = secs
+                        .parse()
+                        .map_err(|_| format!("Invalid interval {}", secs))?;
+                    parsed.interval = Duration::from_secs(secs);
+                }
+                "--move-to" => {
+                    parsed.move_to = Some(RouteTemplate::parse(&args.next().ok_or(USAGE)?)?)
+                }
+                "--unknown-dir" => parsed.unknown_dir = Some(args.next().ok_or(USAGE)?.into()),
+                "--once" => parsed.once = true,
+                flag if flag.starts_with("--") => return Err(format!("Unknown option {}", flag)),
+                _ if parsed.target.is_none() => parsed.target = Some(arg),
+                _ => return Err(USAGE.to_string()),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    fn cache_path(&self) -> Option<PathBuf> {
+        if self.no_cache {
+            return None;
+        }
+        self.cache_path.clone().or_else(default_cache_path)
+    }
+}
+
+fn default_cache_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("file_finch").join("detections.tsv"))
+}
+
+// Ticks the progress bar as the archive is streamed through.
+struct ProgressReader<R> {
+    inner: R,
+    progress_bar: ProgressBar,
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let bytes_read = self.inner.read(buf)?;
+        if bytes_read == 0 {
+            self.progress_bar.finish();
+        } else {
+            self.progress_bar.update(bytes_read);
+        }
+        Ok(bytes_read)
+    }
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let url = "https://data.london.gov.uk/download/9ca66bba-b18c-4d2b-8025-a5fe7d0d66e0/6defa131-f57e-4f86-921d-8d023c98155d/LAEI2019-nox-pm-cold-start-grid-emissions.zip";
-    println!("Fetching and processing ZIP from: {}", url);
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
 
-    let extractor = MuyZipido::new(url, 10240)?.with_progress(Style::Blocks, Colour::Magenta);
+    match args.command.as_str() {
+        "scan" | "manifest" => scan(&args),
+        "watch" => watch(&args),
+        "fetch" => fetch(args.target.as_deref().unwrap_or(DEFAULT_URL)),
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    }
+}
 
-    let mut total_entries = 0;
-    let mut total_bytes = 0;
-    let mut file_type_counts = std::collections::HashMap::new();
-
-    for entry_result in extractor {
-        match entry_result {
-            Ok(entry) => {
-                total_entries += 1;
-                total_bytes += entry.data.len();
-
-                let detected_type = FileFinch::detect(&entry.data);
-
-                *file_type_counts.entry(detected_type).or_insert(0) += 1;
-
-                println!(
-                    "Entry {}: {} ({} bytes) - Type: {}",
-                    total_entries,
-                    entry.filename,
-                    entry.data.len(),
-                    detected_type
-                );
+fn scan(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let root = std::fs::canonicalize(args.target.as_deref().ok_or(USAGE)?)?;
+    let cache_path = args.cache_path();
+    let mut cache = match &cache_path {
+        Some(path) => DetectionCache::load(path)?,
+        None => DetectionCache::new(),
+    };
+
+    // Files that cannot be read are logged and counted, the rest of the scan goes on.
+    let mut unreadable = 0;
+    let mut files = Vec::new();
+    for file in layout::walk_dir(&root) {
+        match file {
+            Ok(path) => files.push(path),
+            Err(e) => {
+                eprintln!("{}", e);
+                unreadable += 1;
             }
+        }
+    }
+    files.sort();
+
+    let manifest = args.command == "manifest";
+    if manifest {
+        println!("path,size,type,status");
+    }
+
+    let mut file_type_counts: HashMap<FileType, usize> = HashMap::new();
+    for path in &files {
+        let detected = cache
+            .analyze(path)
+            .and_then(|result| Ok((result, std::fs::metadata(path)?.len())));
+        let (result, size) = match detected {
+            Ok(detected) => detected,
             Err(e) => {
-                eprintln!("Error processing entry: {}", e);
-                break;
+                eprintln!("{}: {}", path.display(), e);
+                unreadable += 1;
+                continue;
             }
+        };
+        *file_type_counts.entry(result.file_type).or_insert(0) += 1;
+
+        let relative = path.strip_prefix(&root).unwrap_or(path).display();
+        if manifest {
+            println!(
+                "{},{},{},{}",
+                csv_field(&relative.to_string()),
+                size,
+                result.file_type,
+                result.status
+            );
+        } else {
+            println!("{}: {}", relative, result);
+        }
+    }
+
+    if let Some(path) = &cache_path {
+        cache.prune();
+        cache.save(path)?;
+    }
+
+    eprintln!(
+        "{} files, {} from cache, {} detected, {} unreadable",
+        files.len(),
+        cache.hits(),
+        cache.misses(),
+        unreadable
+    );
+
+    if !manifest {
+        println!("\n=== File Type Distribution ===");
+        for (file_type, count) in &file_type_counts {
+            println!("{}: {}", file_type, count);
         }
     }
 
+    if unreadable > 0 {
+        return Err(format!("{} paths could not be read", unreadable).into());
+    }
+    Ok(())
+}
+
+// Polls the top level of a landing directory. A file is only handled once its size has held
+// steady across a poll, so half uploaded files are left alone. Errors with a single file are
+// logged and the file is tried again on the next poll.
+fn watch(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = PathBuf::from(args.target.as_deref().ok_or(USAGE)?);
+    let mut pending: HashMap<PathBuf, u64> = HashMap::new();
+    let mut handled: HashMap<PathBuf, u64> = HashMap::new();
+
+    println!("Watching {}", dir.display());
+
+    loop {
+        let current = match file_sizes(&dir) {
+            Ok(current) => current,
+            Err(e) if args.once => return Err(e.into()),
+            Err(e) => {
+                eprintln!("{}: {}", dir.display(), e);
+                std::thread::sleep(args.interval);
+                continue;
+            }
+        };
+
+        for (path, &size) in &current {
+            let stable = args.once || pending.get(path) == Some(&size);
+            if stable && handled.get(path) != Some(&size) {
+                match route_file(path, args) {
+                    Ok(()) => {
+                        handled.insert(path.clone(), size);
+                    }
+                    Err(e) => eprintln!("{}: {}", path.display(), e),
+                }
+            }
+        }
+
+        handled.retain(|path, _| current.contains_key(path));
+        pending = current;
+
+        if args.once {
+            return Ok(());
+        }
+        std::thread::sleep(args.interval);
+    }
+}
+
+// Sizes of the files directly in `dir`. Entries that vanish or are renamed while being listed,
+// as partial uploads do, are skipped.
+fn file_sizes(dir: &Path) -> std::io::Result<HashMap<PathBuf, u64>> {
+    let mut sizes = HashMap::new();
+    for entry in std::fs::read_dir(dir)? {
+        let Ok(entry) = entry else { continue };
+        if let Ok(metadata) = entry.metadata()
+            && metadata.is_file()
+        {
+            sizes.insert(entry.path(), metadata.len());
+        }
+    }
+    Ok(sizes)
+}
+
+fn route_file(path: &Path, args: &Args) -> std::io::Result<()> {
+    let file_type = match FileFinch::detect_file(path) {
+        Ok(file_type) => file_type,
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            FileType::Unknown
+        }
+    };
+
+    let destination = match (file_type, &args.unknown_dir, &args.move_to) {
+        (FileType::Unknown, Some(unknown_dir), _) => {
+            Some(unknown_dir.join(path.file_name().unwrap_or_default()))
+        }
+        (FileType::Unknown, None, _) => None,
+        (_, _, Some(template)) => Some(template.render(path, file_type)),
+        (_, _, None) => None,
+    };
+
+    match destination {
+        Some(destination) => {
+            let moved_to = route::move_file(path, &destination)?;
+            println!(
+                "{}: {} -> {}",
+                path.display(),
+                file_type,
+                moved_to.display()
+            );
+        }
+        None => println!("{}: {}", path.display(), file_type),
+    }
+
+    Ok(())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn fetch(url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Fetching and processing ZIP from: {}", url);
+
+    let response = reqwest::blocking::get(url)?.error_for_status()?;
+    let progress_bar = ProgressBar::new(response.content_length().map(|len| len as usize))
+        .with_description("Downloading ZIP".to_string())
+        .with_style(Style::Blocks)
+        .with_color(Colour::Magenta);
+    let reader = ProgressReader {
+        inner: response,
+        progress_bar,
+    };
+
+    let mut total_entries = 0;
+    let mut file_type_counts = HashMap::new();
+
+    let detector = Detector::new();
+    archive::walk_with(
+        &detector,
+        reader,
+        DEFAULT_PREFIX_LEN,
+        |name, prefix, result| {
+            total_entries += 1;
+            *file_type_counts.entry(result.file_type).or_insert(0) += 1;
+
+            println!(
+                "Entry {}: {} ({} byte prefix) - Type: {}",
+                total_entries,
+                name,
+                prefix.len(),
+                result
+            );
+
+            ControlFlow::<()>::Continue(())
+        },
+    )?;
+
     println!("\n=== Summary ===");
     println!("Total entries: {}", total_entries);
-    println!("Total bytes processed: {}", total_bytes);
 
     println!("\n=== File Type Distribution ===");
     for (file_type, count) in &file_type_counts {
@@ -0,0 +1,469 @@
+// Just enough of the Parquet footer to pull min/max statistics for date and timestamp columns.
+// The footer is Thrift compact protocol, so this is a tiny hand rolled reader rather than a
+// dependency.
+
+const MAX_DEPTH: usize = 32;
+
+const TYPE_BOOL_TRUE: u8 = 1;
+const TYPE_BOOL_FALSE: u8 = 2;
+const TYPE_BYTE: u8 = 3;
+const TYPE_I16: u8 = 4;
+pub(crate) const TYPE_I32: u8 = 5;
+pub(crate) const TYPE_I64: u8 = 6;
+const TYPE_DOUBLE: u8 = 7;
+pub(crate) const TYPE_BINARY: u8 = 8;
+pub(crate) const TYPE_LIST: u8 = 9;
+const TYPE_SET: u8 = 10;
+const TYPE_MAP: u8 = 11;
+pub(crate) const TYPE_STRUCT: u8 = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TemporalKind {
+    Date,
+    TimestampMillis,
+    TimestampMicros,
+    TimestampNanos,
+}
+
+impl TemporalKind {
+    fn to_seconds(self, raw: &[u8]) -> Option<i64> {
+        match self {
+            TemporalKind::Date => {
+                let days = i32::from_le_bytes(raw.get(..4)?.try_into().ok()?);
+                Some(days as i64 * 86_400)
+            }
+            _ => {
+                let value = i64::from_le_bytes(raw.get(..8)?.try_into().ok()?);
+                let per_second = match self {
+                    TemporalKind::TimestampMillis => 1_000,
+                    TemporalKind::TimestampMicros => 1_000_000,
+                    _ => 1_000_000_000,
+                };
+                Some(value.div_euclid(per_second))
+            }
+        }
+    }
+}
+
+/// Min/max of a date or timestamp column, in seconds since the Unix epoch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ColumnRange {
+    pub name: String,
+    pub min_seconds: i64,
+    pub max_seconds: i64,
+}
+
+/// Reads the footer of a complete Parquet file and returns the range of every date/timestamp column
+/// that carries statistics. Truncated input has no footer, so it yields nothing.
+pub(crate) fn temporal_ranges(bytes: &[u8]) -> Vec<ColumnRange> {
+    footer(bytes)
+        .and_then(|metadata| read_file_metadata(&mut CompactReader::new(metadata)))
+        .unwrap_or_default()
+}
+
+fn footer(bytes: &[u8]) -> Option<&[u8]> {
+    if bytes.len() < 12 || !bytes.starts_with(b"PAR1") || !bytes.ends_with(b"PAR1") {
+        return None;
+    }
+
+    let len_at = bytes.len() - 8;
+    let metadata_len = u32::from_le_bytes(bytes[len_at..len_at + 4].try_into().ok()?) as usize;
+    let start = len_at.checked_sub(metadata_len)?;
+    if start < 4 {
+        return None;
+    }
+
+    Some(&bytes[start..len_at])
+}
+
+#[derive(Default)]
+struct SchemaElement {
+    name: String,
+    num_children: i32,
+    kind: Option<TemporalKind>,
+}
+
+fn read_file_metadata(reader: &mut CompactReader) -> Option<Vec<ColumnRange>> {
+    let mut schema = Vec::new();
+    let mut ranges: Vec<ColumnRange> = Vec::new();
+    let mut columns: Vec<(String, TemporalKind)> = Vec::new();
+
+    reader.read_struct(0, |reader, id, ty| match (id, ty) {
+        (2, TYPE_LIST) => {
+            reader.read_list(|reader, _| {
+                schema.push(read_schema_element(reader)?);
+                Some(())
+            })?;
+            columns = temporal_columns(&schema);
+            Some(true)
+        }
+        (4, TYPE_LIST) => {
+            reader.read_list(|reader, _| read_row_group(reader, &columns, &mut ranges))?;
+            Some(true)
+        }
+        _ => Some(false),
+    })?;
+
+    Some(ranges)
+}
+
+fn read_schema_element(reader: &mut CompactReader) -> Option<SchemaElement> {
+    let mut element = SchemaElement::default();
+    let mut logical = None;
+
+    reader.read_struct(1, |reader, id, ty| match (id, ty) {
+        (4, TYPE_BINARY) => {
+            element.name = String::from_utf8_lossy(reader.binary()?).into_owned();
+            Some(true)
+        }
+        (5, TYPE_I32) => {
+            element.num_children = reader.int()? as i32;
+            Some(true)
+        }
+        (6, TYPE_I32) => {
+            element.kind = match reader.int()? {
+                6 => Some(TemporalKind::Date),
+                9 => Some(TemporalKind::TimestampMillis),
+                10 => Some(TemporalKind::TimestampMicros),
+                _ => None,
+            };
+            Some(true)
+        }
+        (10, TYPE_STRUCT) => {
+            logical = read_logical_type(reader)?;
+            Some(true)
+        }
+        _ => Some(false),
+    })?;
+
+    // The newer LogicalType annotation wins over the legacy ConvertedType one.
+    if logical.is_some() {
+        element.kind = logical;
+    }
+
+    Some(element)
+}
+
+fn read_logical_type(reader: &mut CompactReader) -> Option<Option<TemporalKind>> {
+    let mut kind = None;
+
+    reader.read_struct(2, |reader, id, ty| match (id, ty) {
+        (6, TYPE_STRUCT) => {
+            reader.skip(TYPE_STRUCT, 3)?;
+            kind = Some(TemporalKind::Date);
+            Some(true)
+        }
+        (8, TYPE_STRUCT) => {
+            reader.read_struct(3, |reader, id, ty| match (id, ty) {
+                (2, TYPE_STRUCT) => {
+                    reader.read_struct(4, |reader, id, ty| {
+                        kind = match id {
+                            1 => Some(TemporalKind::TimestampMillis),
+                            2 => Some(TemporalKind::TimestampMicros),
+                            3 => Some(TemporalKind::TimestampNanos),
+                            _ => kind,
+                        };
+                        reader.skip(ty, 5)?;
+                        Some(true)
+                    })?;
+                    Some(true)
+                }
+                _ => Some(false),
+            })?;
+            Some(true)
+        }
+        _ => Some(false),
+    })?;
+
+    Some(kind)
+}
+
+// Schema elements are a flattened depth first tree, so rebuild dotted leaf paths as we go.
+fn temporal_columns(schema: &[SchemaElement]) -> Vec<(String, TemporalKind)> {
+    let mut parents: Vec<(&str, i32)> = Vec::new();
+    let mut columns = Vec::new();
+
+    for element in schema.iter().skip(1) {
+        while parents.last().is_some_and(|(_, remaining)| *remaining == 0) {
+            parents.pop();
+        }
+        if let Some((_, remaining)) = parents.last_mut() {
+            *remaining -= 1;
+        }
+
+        if element.num_children > 0 {
+            parents.push((&element.name, element.num_children));
+            continue;
+        }
+
+        if let Some(kind) = element.kind {
+            let mut path: Vec<&str> = parents.iter().map(|(name, _)| *name).collect();
+            path.push(&element.name);
+            columns.push((path.join("."), kind));
+        }
+    }
+
+    columns
+}
+
+fn read_row_group(
+    reader: &mut CompactReader,
+    columns: &[(String, TemporalKind)],
+    ranges: &mut Vec<ColumnRange>,
+) -> Option<()> {
+    reader.read_struct(1, |reader, id, ty| match (id, ty) {
+        (1, TYPE_LIST) => {
+            reader.read_list(|reader, _| read_column_chunk(reader, columns, ranges))?;
+            Some(true)
+        }
+        _ => Some(false),
+    })
+}
+
+fn read_column_chunk(
+    reader: &mut CompactReader,
+    columns: &[(String, TemporalKind)],
+    ranges: &mut Vec<ColumnRange>,
+) -> Option<()> {
+    reader.read_struct(3, |reader, id, ty| match (id, ty) {
+        (3, TYPE_STRUCT) => {
+            read_column_metadata(reader, columns, ranges)?;
+            Some(true)
+        }
+        _ => Some(false),
+    })
+}
+
+fn read_column_metadata(
+    reader: &mut CompactReader,
+    columns: &[(String, TemporalKind)],
+    ranges: &mut Vec<ColumnRange>,
+) -> Option<()> {
+    let mut path = Vec::new();
+    let mut legacy = (None, None);
+    let mut current = (None, None);
+
+    reader.read_struct(4, |reader, id, ty| match (id, ty) {
+        (3, TYPE_LIST) => {
+            reader.read_list(|reader, _| {
+                path.push(String::from_utf8_lossy(reader.binary()?).into_owned());
+                Some(())
+            })?;
+            Some(true)
+        }
+        (12, TYPE_STRUCT) => {
+            reader.read_struct(5, |reader, id, ty| {
+                let slot = match (id, ty) {
+                    (1, TYPE_BINARY) => &mut legacy.1,
+                    (2, TYPE_BINARY) => &mut legacy.0,
+                    (5, TYPE_BINARY) => &mut current.1,
+                    (6, TYPE_BINARY) => &mut current.0,
+                    _ => return Some(false),
+                };
+                *slot = Some(reader.binary()?.to_vec());
+                Some(true)
+            })?;
+            Some(true)
+        }
+        _ => Some(false),
+    })?;
+
+    let name = path.join(".");
+    let Some((_, kind)) = columns.iter().find(|(column, _)| *column == name) else {
+        return Some(());
+    };
+
+    let (Some(min), Some(max)) = (current.0.or(legacy.0), current.1.or(legacy.1)) else {
+        return Some(());
+    };
+
+    let (Some(min_seconds), Some(max_seconds)) = (kind.to_seconds(&min), kind.to_seconds(&max))
+    else {
+        return Some(());
+    };
+
+    match ranges.iter_mut().find(|range| range.name == name) {
+        Some(range) => {
+            range.min_seconds = range.min_seconds.min(min_seconds);
+            range.max_seconds = range.max_seconds.max(max_seconds);
+        }
+        None => ranges.push(ColumnRange {
+            name,
+            min_seconds,
+            max_seconds,
+        }),
+    }
+
+    Some(())
+}
+
+struct CompactReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> CompactReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.bytes.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(len)?;
+        let slice = self.bytes.get(self.pos..end)?;
+        self.pos = end;
+        Some(slice)
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn int(&mut self) -> Option<i64> {
+        let value = self.varint()?;
+        Some((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    fn binary(&mut self) -> Option<&'a [u8]> {
+        let len = self.varint()? as usize;
+        self.take(len)
+    }
+
+    /// Calls `field` for each field of a struct. The callback returns `false` for fields it did not
+    /// consume so they can be skipped.
+    fn read_struct<F>(&mut self, depth: usize, mut field: F) -> Option<()>
+    where
+        F: FnMut(&mut Self, i16, u8) -> Option<bool>,
+    {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        let mut last_id = 0i16;
+        loop {
+            let header = self.byte()?;
+            let ty = header & 0x0F;
+            if ty == 0 {
+                return Some(());
+            }
+
+            let delta = (header >> 4) as i16;
+            last_id = if delta == 0 {
+                self.int()? as i16
+            } else {
+                last_id.checked_add(delta)?
+            };
+
+            if !field(self, last_id, ty)? {
+                self.skip(ty, depth + 1)?;
+            }
+        }
+    }
+
+    fn read_list<F>(&mut self, mut element: F) -> Option<()>
+    where
+        F: FnMut(&mut Self, u8) -> Option<()>,
+    {
+        let (len, ty) = self.list_header()?;
+        for _ in 0..len {
+            element(self, ty)?;
+        }
+        Some(())
+    }
+
+    fn list_header(&mut self) -> Option<(usize, u8)> {
+        let header = self.byte()?;
+        let len = match header >> 4 {
+            15 => self.varint()? as usize,
+            short => short as usize,
+        };
+        Some((len, header & 0x0F))
+    }
+
+    fn skip(&mut self, ty: u8, depth: usize) -> Option<()> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        match ty {
+            TYPE_BOOL_TRUE | TYPE_BOOL_FALSE => {}
+            TYPE_BYTE => {
+                self.byte()?;
+            }
+            TYPE_I16 | TYPE_I32 | TYPE_I64 => {
+                self.varint()?;
+            }
+            TYPE_DOUBLE => {
+                self.take(8)?;
+            }
+            TYPE_BINARY => {
+                self.binary()?;
+            }
+            TYPE_LIST | TYPE_SET => {
+                self.read_list(|reader, ty| reader.skip_element(ty, depth + 1))?;
+            }
+            TYPE_MAP => {
+                let len = self.varint()? as usize;
+                if len > 0 {
+                    let types = self.byte()?;
+                    for _ in 0..len {
+                        self.skip_element(types >> 4, depth + 1)?;
+                        self.skip_element(types & 0x0F, depth + 1)?;
+                    }
+                }
+            }
+            TYPE_STRUCT => {
+                self.read_struct(depth, |_, _, _| Some(false))?;
+            }
+            _ => return None,
+        }
+
+        Some(())
+    }
+
+    // Inside lists and maps booleans take a whole byte rather than living in the field header.
+    fn skip_element(&mut self, ty: u8, depth: usize) -> Option<()> {
+        match ty {
+            TYPE_BOOL_TRUE | TYPE_BOOL_FALSE => self.byte().map(|_| ()),
+            _ => self.skip(ty, depth),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples;
+
+    #[test]
+    fn test_temporal_ranges_from_footer() {
+        let bytes = examples::parquet_with_date_stats(18_262, 18_627);
+
+        assert_eq!(
+            temporal_ranges(&bytes),
+            vec![ColumnRange {
+                name: "observed".to_string(),
+                min_seconds: 18_262 * 86_400,
+                max_seconds: 18_627 * 86_400,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_temporal_ranges_without_footer() {
+        assert!(temporal_ranges(b"PAR1\x00\x00\x00\x00").is_empty());
+    }
+}
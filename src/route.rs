@@ -0,0 +1,151 @@
+use crate::FileType;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Segment {
+    Literal(String),
+    Type,
+    Filename,
+    Stem,
+    Ext,
+}
+
+/// A destination path template such as `incoming/{type}/{filename}`.
+///
+/// `{type}` is the lowercased detected type, `{filename}` the original file name, and `{stem}` and
+/// `{ext}` its parts either side of the last dot.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RouteTemplate {
+    segments: Vec<Segment>,
+}
+
+impl RouteTemplate {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            let close = rest[open..]
+                .find('}')
+                .ok_or_else(|| format!("Unclosed placeholder in {}", template))?;
+            if open > 0 {
+                segments.push(Segment::Literal(rest[..open].to_string()));
+            }
+            segments.push(match &rest[open + 1..open + close] {
+                "type" => Segment::Type,
+                "filename" => Segment::Filename,
+                "stem" => Segment::Stem,
+                "ext" => Segment::Ext,
+                name => return Err(format!("Unknown placeholder {{{}}} in {}", name, template)),
+            });
+            rest = &rest[open + close + 1..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.to_string()));
+        }
+
+        Ok(RouteTemplate { segments })
+    }
+
+    pub fn render(&self, path: &Path, file_type: FileType) -> PathBuf {
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        let (stem, ext) = filename.rsplit_once('.').unwrap_or((&filename, ""));
+
+        // One pass over the parsed template, so braces in the file name are never expanded.
+        let mut rendered = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => rendered.push_str(text),
+                Segment::Type => rendered.push_str(&file_type.to_string().to_lowercase()),
+                Segment::Filename => rendered.push_str(&filename),
+                Segment::Stem => rendered.push_str(stem),
+                Segment::Ext => rendered.push_str(ext),
+            }
+        }
+        PathBuf::from(rendered)
+    }
+}
+
+/// Moves `from` to `to`, creating parent directories. An existing file at `to` is never
+/// overwritten, a `-1`, `-2`, ... suffix is added to the stem instead. Returns where the file ended
+/// up.
+pub fn move_file(from: &Path, to: &Path) -> io::Result<PathBuf> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let destination = unused_path(to);
+    if fs::rename(from, &destination).is_err() {
+        // Renames fail across filesystems, fall back to copy and delete.
+        fs::copy(from, &destination)?;
+        fs::remove_file(from)?;
+    }
+
+    Ok(destination)
+}
+
+fn unused_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let ext = path.extension().map(|e| e.to_string_lossy().into_owned());
+
+    (1..)
+        .map(|n| {
+            let name = match &ext {
+                Some(ext) => format!("{}-{}.{}", stem, n, ext),
+                None => format!("{}-{}", stem, n),
+            };
+            path.with_file_name(name)
+        })
+        .find(|candidate| !candidate.exists())
+        .expect("ran out of suffixes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_template() {
+        let template = RouteTemplate::parse("incoming/{type}/{stem}-checked.{ext}").unwrap();
+
+        assert_eq!(
+            template.render(Path::new("/landing/roads.geojson"), FileType::Geojson),
+            PathBuf::from("incoming/geojson/roads-checked.geojson")
+        );
+        assert_eq!(
+            template.render(Path::new("a{ext}.csv"), FileType::Csv),
+            PathBuf::from("incoming/csv/a{ext}-checked.csv")
+        );
+        assert!(RouteTemplate::parse("incoming/{kind}/{filename}").is_err());
+        assert!(RouteTemplate::parse("incoming/{type").is_err());
+    }
+
+    #[test]
+    fn test_move_file_keeps_existing() {
+        let dir = std::env::temp_dir().join(format!("file_finch_route_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let template =
+            RouteTemplate::parse(&format!("{}/out/{{type}}/{{filename}}", dir.display())).unwrap();
+
+        for _ in 0..2 {
+            let source = dir.join("sites.csv");
+            fs::write(&source, b"a,b\n1,2\n").unwrap();
+            move_file(&source, &template.render(&source, FileType::Csv)).unwrap();
+        }
+
+        assert!(dir.join("out/csv/sites.csv").exists());
+        assert!(dir.join("out/csv/sites-1.csv").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,385 @@
+use crate::text::{split_record, string_members, utf8_prefix};
+use crate::{FileType, parquet};
+use std::fmt;
+
+/// How many CSV data rows are sampled when looking for date columns.
+const CSV_SAMPLE_ROWS: usize = 200;
+
+/// A calendar date and time of day. UTC offsets are accepted when parsing but not applied.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Timestamp {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl Timestamp {
+    /// Parses `YYYY-MM-DD` or `YYYY/MM/DD`, optionally followed by a `T` or space separated
+    /// `HH:MM[:SS[.fff]]` time with an optional `Z`/`±HH:MM` suffix.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let bytes = value.as_bytes();
+        if bytes.len() < 10 || !matches!(bytes[4], b'-' | b'/') || bytes[7] != bytes[4] {
+            return None;
+        }
+
+        let year = digits(&bytes[0..4])? as i32;
+        let month = digits(&bytes[5..7])? as u8;
+        let day = digits(&bytes[8..10])? as u8;
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+
+        let mut timestamp = Timestamp {
+            year,
+            month,
+            day,
+            hour: 0,
+            minute: 0,
+            second: 0,
+        };
+
+        let rest = &bytes[10..];
+        if rest.is_empty() {
+            return Some(timestamp);
+        }
+        if !matches!(rest[0], b'T' | b' ') || rest.len() < 6 || rest[3] != b':' {
+            return None;
+        }
+
+        timestamp.hour = digits(&rest[1..3])? as u8;
+        timestamp.minute = digits(&rest[4..6])? as u8;
+        let mut rest = &rest[6..];
+        if rest.first() == Some(&b':') {
+            timestamp.second = digits(rest.get(1..3)?)? as u8;
+            rest = &rest[3..];
+            if rest.first() == Some(&b'.') {
+                let fraction = rest[1..].iter().take_while(|b| b.is_ascii_digit()).count();
+                rest = &rest[1 + fraction..];
+            }
+        }
+
+        if timestamp.hour > 23 || timestamp.minute > 59 || timestamp.second > 60 {
+            return None;
+        }
+
+        match rest {
+            [] | [b'Z'] => Some(timestamp),
+            [b'+' | b'-', h1, h2, b':', m1, m2] | [b'+' | b'-', h1, h2, m1, m2] => {
+                digits(&[*h1, *h2, *m1, *m2]).map(|_| timestamp)
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts seconds since the Unix epoch (UTC) into a calendar timestamp.
+    pub fn from_unix_seconds(seconds: i64) -> Self {
+        let days = seconds.div_euclid(86_400);
+        let time = seconds.rem_euclid(86_400);
+
+        // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        Timestamp {
+            year: year as i32,
+            month: month as u8,
+            day: day as u8,
+            hour: (time / 3600) as u8,
+            minute: (time % 3600 / 60) as u8,
+            second: (time % 60) as u8,
+        }
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// Earliest and latest timestamps found in a dataset, and the columns they were taken from.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TemporalExtent {
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub columns: Vec<String>,
+}
+
+impl TemporalExtent {
+    fn include(
+        extent: &mut Option<TemporalExtent>,
+        column: &str,
+        start: Timestamp,
+        end: Timestamp,
+    ) {
+        match extent {
+            Some(extent) => {
+                extent.start = extent.start.min(start);
+                extent.end = extent.end.max(end);
+                if !extent.columns.iter().any(|c| c == column) {
+                    extent.columns.push(column.to_string());
+                }
+            }
+            None => {
+                *extent = Some(TemporalExtent {
+                    start,
+                    end,
+                    columns: vec![column.to_string()],
+                })
+            }
+        }
+    }
+}
+
+/// Estimates the temporal extent of CSV, Parquet and GeoJSON data.
+///
+/// CSV rows and GeoJSON string properties are sampled, and a column only counts when every
+/// non-empty sampled value parses as a date. Parquet uses the footer statistics of date and
+/// timestamp columns, so it needs the whole file rather than a prefix. CSV fields are split on
+/// `delimiter`.
+pub fn sniff(file_type: FileType, bytes: &[u8], delimiter: u8) -> Option<TemporalExtent> {
+    match file_type {
+        FileType::Csv => sniff_csv(bytes, delimiter),
+        FileType::Geojson => sniff_geojson(bytes),
+        FileType::Parquet => sniff_parquet(bytes),
+        _ => None,
+    }
+}
+
+// Tracks one sampled column: it stays a date column only while every non-empty value parses.
+#[derive(Clone, Default)]
+struct DateColumn {
+    range: Option<(Timestamp, Timestamp)>,
+    rejected: bool,
+}
+
+impl DateColumn {
+    fn observe(&mut self, value: &str) {
+        if self.rejected || value.trim().is_empty() {
+            return;
+        }
+        match Timestamp::parse(value) {
+            Some(ts) => {
+                let range = self.range.get_or_insert((ts, ts));
+                range.0 = range.0.min(ts);
+                range.1 = range.1.max(ts);
+            }
+            None => self.rejected = true,
+        }
+    }
+
+    fn extend(&self, extent: &mut Option<TemporalExtent>, name: &str) {
+        if let (Some((start, end)), false) = (self.range, self.rejected) {
+            TemporalExtent::include(extent, name, start, end);
+        }
+    }
+}
+
+fn sniff_csv(bytes: &[u8], delimiter: u8) -> Option<TemporalExtent> {
+    let text = utf8_prefix(bytes);
+    let lines: Vec<&str> = text.lines().take(CSV_SAMPLE_ROWS + 1).collect();
+
+    let (header, rows) = lines.split_first()?;
+    let header = split_record(header, delimiter);
+    let mut columns = vec![DateColumn::default(); header.len()];
+
+    // A prefix cut mid row would make that row's last value look like a non date, so a final
+    // line with no newline after it only counts when it leaves every date column standing.
+    let complete = text.ends_with('\n') || text.lines().nth(lines.len()).is_some();
+    let (rows, last) = match rows.split_last() {
+        Some((last, rest)) if !complete => (rest, Some(last)),
+        _ => (rows, None),
+    };
+
+    let observe = |columns: &mut [DateColumn], row: &str| {
+        for (column, value) in columns.iter_mut().zip(split_record(row, delimiter)) {
+            column.observe(value);
+        }
+    };
+    for row in rows {
+        observe(&mut columns, row);
+    }
+    if let Some(last) = last {
+        let mut with_last = columns.clone();
+        observe(&mut with_last, last);
+        let keeps_dates = columns
+            .iter()
+            .zip(&with_last)
+            .all(|(before, after)| before.rejected || before.range.is_none() || !after.rejected);
+        if keeps_dates {
+            columns = with_last;
+        }
+    }
+
+    let mut extent = None;
+    for (name, column) in header.iter().zip(&columns) {
+        column.extend(&mut extent, name);
+    }
+    extent
+}
+
+fn sniff_geojson(bytes: &[u8]) -> Option<TemporalExtent> {
+    let text = utf8_prefix(bytes);
+    let mut keys: Vec<(&str, DateColumn)> = Vec::new();
+
+    for (key, value) in string_members(text) {
+        match keys.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, column)) => column.observe(value),
+            None => {
+                let mut column = DateColumn::default();
+                column.observe(value);
+                keys.push((key, column));
+            }
+        }
+    }
+
+    let mut extent = None;
+    for (key, column) in &keys {
+        column.extend(&mut extent, key);
+    }
+    extent
+}
+
+fn sniff_parquet(bytes: &[u8]) -> Option<TemporalExtent> {
+    let mut extent = None;
+    for range in parquet::temporal_ranges(bytes) {
+        TemporalExtent::include(
+            &mut extent,
+            &range.name,
+            Timestamp::from_unix_seconds(range.min_seconds),
+            Timestamp::from_unix_seconds(range.max_seconds),
+        );
+    }
+    extent
+}
+
+fn digits(bytes: &[u8]) -> Option<u32> {
+    bytes.iter().try_fold(0u32, |acc, &b| {
+        b.is_ascii_digit().then(|| acc * 10 + (b - b'0') as u32)
+    })
+}
+
+fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples;
+
+    fn ts(year: i32, month: u8, day: u8) -> Timestamp {
+        Timestamp {
+            year,
+            month,
+            day,
+            hour: 0,
+            minute: 0,
+            second: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_timestamps() {
+        assert_eq!(Timestamp::parse("2021-03-04"), Some(ts(2021, 3, 4)));
+        assert_eq!(Timestamp::parse("2021/03/04"), Some(ts(2021, 3, 4)));
+        assert_eq!(
+            Timestamp::parse("2021-03-04T05:06:07.123+01:00").map(|t| t.to_string()),
+            Some("2021-03-04T05:06:07".to_string())
+        );
+        assert_eq!(Timestamp::parse("2021-02-29"), None);
+        assert_eq!(Timestamp::parse("2021-03-04x"), None);
+        assert_eq!(Timestamp::parse("not a date"), None);
+    }
+
+    #[test]
+    fn test_from_unix_seconds() {
+        assert_eq!(Timestamp::from_unix_seconds(0), ts(1970, 1, 1));
+        assert_eq!(
+            Timestamp::from_unix_seconds(18_262 * 86_400),
+            ts(2020, 1, 1)
+        );
+        assert_eq!(Timestamp::from_unix_seconds(-86_400), ts(1969, 12, 31));
+    }
+
+    #[test]
+    fn test_sniff_csv() {
+        let csv =
+            b"id,observed,note\n1,2020-05-01,2020 survey\n2,2019-12-31,\n3,2021-01-02T10:00:00,x\n";
+        let extent = sniff(FileType::Csv, csv, b',').unwrap();
+
+        assert_eq!(extent.start, ts(2019, 12, 31));
+        assert_eq!(extent.end.to_string(), "2021-01-02T10:00:00");
+        assert_eq!(extent.columns, vec!["observed"]);
+    }
+
+    #[test]
+    fn test_sniff_csv_without_trailing_newline() {
+        let extent = sniff(
+            FileType::Csv,
+            b"site,date\nA,2022-04-01\nB,2023-09-30",
+            b',',
+        )
+        .unwrap();
+
+        assert_eq!(extent.start, ts(2022, 4, 1));
+        assert_eq!(extent.end, ts(2023, 9, 30));
+    }
+
+    #[test]
+    fn test_sniff_csv_truncated_row() {
+        let csv = b"id,observed\n1,2020-05-01\n2,2020-06-01\n3,2020-0";
+        let extent = sniff(FileType::Csv, csv, b',').unwrap();
+
+        assert_eq!(extent.end, ts(2020, 6, 1));
+    }
+
+    #[test]
+    fn test_sniff_geojson() {
+        let geojson = br#"{"type":"FeatureCollection","features":[
+            {"type":"Feature","properties":{"name":"A","surveyed":"2018-07-01"},"geometry":null},
+            {"type":"Feature","properties":{"name":"B","surveyed":"2016-01-15"},"geometry":null}
+        ]}"#;
+        let extent = sniff(FileType::Geojson, geojson, b',').unwrap();
+
+        assert_eq!(extent.start, ts(2016, 1, 15));
+        assert_eq!(extent.end, ts(2018, 7, 1));
+        assert_eq!(extent.columns, vec!["surveyed"]);
+    }
+
+    #[test]
+    fn test_sniff_parquet() {
+        let bytes = examples::parquet_with_date_stats(18_262, 18_627);
+        let extent = sniff(FileType::Parquet, &bytes, b',').unwrap();
+
+        assert_eq!(extent.start, ts(2020, 1, 1));
+        assert_eq!(extent.end, ts(2020, 12, 31));
+        assert_eq!(extent.columns, vec!["observed"]);
+    }
+
+    #[test]
+    fn test_sniff_without_dates() {
+        assert_eq!(sniff(FileType::Csv, b"a,b\n1,2\n", b','), None);
+        assert_eq!(sniff(FileType::Png, b"2020-01-01", b','), None);
+    }
+}
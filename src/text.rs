@@ -0,0 +1,91 @@
+// Small text helpers shared by the sniffers that sample CSV and GeoJSON content.
+
+// Callers often only have the first chunk of a file, which may end mid character.
+pub(crate) fn utf8_prefix(bytes: &[u8]) -> &str {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => std::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default(),
+    }
+}
+
+pub(crate) fn split_record(line: &str, delimiter: u8) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c == delimiter as char && !quoted => {
+                fields.push(unquote(&line[start..i]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(unquote(&line[start..]));
+    fields
+}
+
+fn unquote(field: &str) -> &str {
+    let trimmed = field.trim();
+    trimmed
+        .strip_prefix('"')
+        .and_then(|f| f.strip_suffix('"'))
+        .unwrap_or(trimmed)
+}
+
+/// Yields every `"key": "string value"` pair in a JSON document, without escapes resolved.
+pub(crate) fn string_members(text: &str) -> impl Iterator<Item = (&str, &str)> {
+    let mut strings = JsonStrings { text, pos: 0 };
+    let mut pending_key: Option<&str> = None;
+
+    std::iter::from_fn(move || {
+        while let Some((value, followed_by_colon, is_value)) = strings.next_string() {
+            if followed_by_colon {
+                pending_key = Some(value);
+            } else if let (Some(key), true) = (pending_key.take(), is_value) {
+                return Some((key, value));
+            }
+        }
+        None
+    })
+}
+
+struct JsonStrings<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> JsonStrings<'a> {
+    // Returns the next string literal, whether a colon follows it (it is a key) and whether a
+    // colon came directly before it (it is a member value).
+    fn next_string(&mut self) -> Option<(&'a str, bool, bool)> {
+        let bytes = self.text.as_bytes();
+        let open = self.pos + bytes[self.pos..].iter().position(|&b| b == b'"')?;
+        let is_value = bytes[..open]
+            .iter()
+            .rev()
+            .find(|b| !b.is_ascii_whitespace())
+            == Some(&b':');
+
+        let mut i = open + 1;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'"' => break,
+                _ => i += 1,
+            }
+        }
+        if i >= bytes.len() {
+            self.pos = bytes.len();
+            return None;
+        }
+
+        self.pos = i + 1;
+        let followed_by_colon =
+            bytes[self.pos..].iter().find(|b| !b.is_ascii_whitespace()) == Some(&b':');
+
+        Some((&self.text[open + 1..i], followed_by_colon, is_value))
+    }
+}
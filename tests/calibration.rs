@@ -0,0 +1,148 @@
+//! Runs the detector over the labelled corpus in `tests/corpus` and fails if precision or recall for
+//! any format drops below its threshold.
+//!
+//! Each corpus file sits in a directory named after its expected type (`csv`, `geojson`,
+//! `unknown`, ...). Run with `cargo test --test calibration -- --nocapture` to see the full report.
+
+use file_finch::{FileFinch, FileType};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Minimum (precision, recall) per format, set at what the detector scores today. Raise these as
+/// heuristics improve, never lower them to make a change pass. `Html` has no row: the default
+/// profile never reports it, so detecting it at all fails the run.
+const THRESHOLDS: &[(FileType, f64, f64)] = &[
+    (FileType::Geopackage, 1.0, 1.0),
+    (FileType::Shapefile, 1.0, 1.0),
+    (FileType::Geojson, 1.0, 1.0),
+    (FileType::Excel, 0.66, 1.0),
+    (FileType::Csv, 0.8, 1.0),
+    (FileType::Parquet, 1.0, 1.0),
+    (FileType::Arrow, 1.0, 1.0),
+    (FileType::Png, 1.0, 1.0),
+    (FileType::Gtfs, 1.0, 1.0),
+    (FileType::Unknown, 1.0, 0.75),
+];
+
+const MIN_ACCURACY: f64 = 0.85;
+
+#[derive(Default)]
+struct Tally {
+    true_positives: usize,
+    false_positives: usize,
+    false_negatives: usize,
+}
+
+impl Tally {
+    fn precision(&self) -> f64 {
+        ratio(
+            self.true_positives,
+            self.true_positives + self.false_positives,
+        )
+    }
+
+    fn recall(&self) -> f64 {
+        ratio(
+            self.true_positives,
+            self.true_positives + self.false_negatives,
+        )
+    }
+}
+
+// A format with nothing to get wrong scores perfectly.
+fn ratio(hits: usize, total: usize) -> f64 {
+    if total == 0 {
+        1.0
+    } else {
+        hits as f64 / total as f64
+    }
+}
+
+fn corpus() -> Vec<(FileType, PathBuf)> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let mut samples = Vec::new();
+
+    for label_dir in std::fs::read_dir(&root).expect("corpus directory") {
+        let label_dir = label_dir.unwrap().path();
+        let label = label_dir.file_name().unwrap().to_string_lossy();
+        let expected: FileType = label
+            .parse()
+            .unwrap_or_else(|e| panic!("corpus directory {}: {}", label, e));
+
+        for sample in std::fs::read_dir(&label_dir).unwrap() {
+            samples.push((expected, sample.unwrap().path()));
+        }
+    }
+
+    samples.sort_by(|a, b| a.1.cmp(&b.1));
+    samples
+}
+
+#[test]
+fn calibration() {
+    let samples = corpus();
+    let mut tallies: HashMap<FileType, Tally> = HashMap::new();
+    let mut correct = 0;
+
+    for (expected, path) in &samples {
+        let detected = FileFinch::detect_file(path).unwrap();
+
+        if detected == *expected {
+            correct += 1;
+            tallies.entry(detected).or_default().true_positives += 1;
+        } else {
+            println!(
+                "MISS {}: expected {}, detected {}",
+                path.strip_prefix(env!("CARGO_MANIFEST_DIR"))
+                    .unwrap()
+                    .display(),
+                expected,
+                detected
+            );
+            tallies.entry(detected).or_default().false_positives += 1;
+            tallies.entry(*expected).or_default().false_negatives += 1;
+        }
+    }
+
+    let accuracy = ratio(correct, samples.len());
+    println!("\n{:<12} {:>9} {:>9}", "format", "precision", "recall");
+
+    let mut failures = Vec::new();
+    for &(file_type, min_precision, min_recall) in THRESHOLDS {
+        let tally = tallies.remove(&file_type).unwrap_or_default();
+        let (precision, recall) = (tally.precision(), tally.recall());
+        println!(
+            "{:<12} {:>9.2} {:>9.2}",
+            file_type.to_string(),
+            precision,
+            recall
+        );
+
+        if precision < min_precision {
+            failures.push(format!(
+                "{} precision {:.2} < {:.2}",
+                file_type, precision, min_precision
+            ));
+        }
+        if recall < min_recall {
+            failures.push(format!(
+                "{} recall {:.2} < {:.2}",
+                file_type, recall, min_recall
+            ));
+        }
+    }
+    println!("accuracy {:.2} over {} samples", accuracy, samples.len());
+
+    if accuracy < MIN_ACCURACY {
+        failures.push(format!("accuracy {:.2} < {:.2}", accuracy, MIN_ACCURACY));
+    }
+    for file_type in tallies.keys() {
+        failures.push(format!("{} has no threshold", file_type));
+    }
+
+    assert!(
+        failures.is_empty(),
+        "calibration regressed:\n{}",
+        failures.join("\n")
+    );
+}